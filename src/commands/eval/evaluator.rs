@@ -0,0 +1,734 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::functions;
+use super::number::{BigInt, Rational};
+use super::parser::{cast_target_error, Expr, Operator, Statement, UnaryOperator};
+use super::units::{self, Dimension, Unit};
+use super::EvalError;
+use crate::utils::datetime::{self, TimeZone};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    // 有理数で厳密に表せる値
+    Exact(Rational),
+    // sqrt や sin などの結果。f64 で近似する
+    Approx(f64),
+}
+
+impl Value {
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Exact(n) => n.to_f64(),
+            Value::Approx(x) => *x,
+        }
+    }
+
+    /// 近似値も f64 の値そのものとして有理数にする。NaN と無限大は None
+    pub fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Value::Exact(n) => Some(n.clone()),
+            Value::Approx(x) => Rational::from_f64(*x),
+        }
+    }
+
+    fn neg(&self) -> Value {
+        match self {
+            Value::Exact(n) => Value::Exact(n.neg()),
+            Value::Approx(x) => Value::Approx(-x),
+        }
+    }
+}
+
+// `in` で変換したときに表示に使う単位
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisplayUnit {
+    pub name: String,
+    // 基本単位でいくつ分か
+    pub factor: Value,
+    pub offset: Option<Rational>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Quantity {
+    // 基本単位で表した値。日時は UNIX 時間の秒数
+    pub value: Value,
+    pub dimension: Dimension,
+    pub unit: Option<DisplayUnit>,
+    // 日時のときは表示に使うタイムゾーンを持つ
+    pub instant: Option<TimeZone>,
+    // リストのときの要素。単位は全ての要素で共通で、value は使わない
+    pub items: Option<Vec<Value>>,
+}
+
+impl Quantity {
+    pub fn new(value: Value, dimension: Dimension) -> Self {
+        Quantity {
+            value,
+            dimension,
+            unit: None,
+            instant: None,
+            items: None,
+        }
+    }
+
+    pub fn list(items: Vec<Value>, dimension: Dimension) -> Self {
+        Quantity {
+            items: Some(items),
+            ..Quantity::new(Value::Exact(Rational::from_i64(0)), dimension)
+        }
+    }
+
+    pub fn instant(timestamp: Value, time_zone: TimeZone) -> Self {
+        Quantity {
+            instant: Some(time_zone),
+            ..Quantity::number(timestamp)
+        }
+    }
+
+    pub fn number(value: Value) -> Self {
+        Quantity::new(value, Dimension::none())
+    }
+
+    /// 表示用の単位で表した値
+    pub fn value_in_unit(&self, mode: Mode) -> Result<Value, EvalError> {
+        let unit = match &self.unit {
+            Some(unit) => unit,
+            None => return Ok(self.value.clone()),
+        };
+        let value = match &unit.offset {
+            Some(offset) => binary(
+                Operator::Sub,
+                self.value.clone(),
+                Value::Exact(offset.clone()),
+                mode,
+            )?,
+            None => self.value.clone(),
+        };
+        binary(Operator::Div, value, unit.factor.clone(), mode)
+    }
+}
+
+// 代入した変数。ユーザーごとに持つ
+pub type Environment = HashMap<String, Quantity>;
+
+pub struct Context<'a> {
+    pub mode: Mode,
+    // now と today の基準
+    pub now: DateTime<Utc>,
+    pub env: &'a Environment,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Mode {
+    // 有理数で厳密に計算し、分数と小数の両方で表示する。分子と分母は i64 に収まる範囲まで
+    #[default]
+    Rational,
+    // Rational と同じ範囲で計算し、小数だけを表示する
+    Decimal,
+    // 任意精度で計算する
+    Big,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "rational" => Some(Mode::Rational),
+            "decimal" => Some(Mode::Decimal),
+            "big" => Some(Mode::Big),
+            _ => None,
+        }
+    }
+}
+
+// Big モードでも際限なく大きな数を作らないようにする上限 (約 2500 桁)。大きいほど 1 回の計算が重くなる
+const MAX_BITS: u64 = 8192;
+
+pub fn overflow() -> EvalError {
+    EvalError::new("オーバーフローしました (overflow)。mode:big なら任意精度で計算できます")
+}
+
+fn too_large() -> EvalError {
+    EvalError::new("計算結果が大きすぎます")
+}
+
+pub fn division_by_zero() -> EvalError {
+    EvalError::new("0 で割ることはできません (division by zero)")
+}
+
+pub fn not_real() -> EvalError {
+    EvalError::new("計算結果が実数になりません")
+}
+
+pub fn dimension_mismatch(left: &Dimension, right: &Dimension) -> EvalError {
+    EvalError::new(&format!("単位の次元が合いません: {} と {}", left, right))
+}
+
+fn dimension_too_large() -> EvalError {
+    EvalError::new("単位の指数が大きすぎます")
+}
+
+fn unitless(quantity: Quantity, what: &str) -> Result<Value, EvalError> {
+    if quantity.dimension.is_none() {
+        Ok(quantity.value)
+    } else {
+        Err(EvalError::new(&format!(
+            "{}は単位の付いていない数のみ計算できます",
+            what
+        )))
+    }
+}
+
+// モードごとの範囲に収まっているかを確かめる
+fn check(value: Rational, mode: Mode) -> Result<Rational, EvalError> {
+    match mode {
+        Mode::Rational | Mode::Decimal => {
+            if value.numerator().to_i64().is_none() || value.denominator().to_i64().is_none() {
+                return Err(overflow());
+            }
+        }
+        Mode::Big => {
+            if value.bits() > MAX_BITS {
+                return Err(too_large());
+            }
+        }
+    }
+    Ok(value)
+}
+
+// 近似値は NaN や無限大になっていないかを確かめる
+pub fn approx(x: f64) -> Result<Value, EvalError> {
+    if x.is_nan() {
+        Err(not_real())
+    } else if x.is_infinite() {
+        Err(too_large())
+    } else {
+        Ok(Value::Approx(x))
+    }
+}
+
+pub fn check_value(value: Value, mode: Mode) -> Result<Value, EvalError> {
+    match value {
+        Value::Exact(n) => Ok(Value::Exact(check(n, mode)?)),
+        Value::Approx(x) => approx(x),
+    }
+}
+
+fn pow(base: &Rational, exp: &Rational, mode: Mode) -> Result<Value, EvalError> {
+    // 整数乗以外は厳密に計算できないので近似する
+    if !exp.is_integer() {
+        return approx(base.to_f64().powf(exp.to_f64()));
+    }
+    let exp = match exp.to_i64() {
+        Some(exp) => exp,
+        None if base.bits() <= 1 && base.is_integer() => {
+            // 0, 1, -1 は指数がどれだけ大きくても結果が決まる
+            let even = exp
+                .numerator()
+                .div_rem(&BigInt::from_u64(2))
+                .map(|(_, r)| r.is_zero());
+            let small = if even == Some(true) { 2 } else { 1 };
+            let small = if exp.is_negative() { -small } else { small };
+            return pow(base, &Rational::from_i64(small), mode);
+        }
+        None => return Err(overflow_or_too_large(mode)),
+    };
+
+    // 計算する前に結果の大きさを見積もる
+    let limit = match mode {
+        Mode::Big => MAX_BITS,
+        _ => 64,
+    };
+    if base.bits() > 1
+        && base
+            .bits()
+            .saturating_sub(1)
+            .saturating_mul(exp.unsigned_abs())
+            > limit
+    {
+        return Err(overflow_or_too_large(mode));
+    }
+
+    match base.pow(exp) {
+        Some(value) => Ok(Value::Exact(check(value, mode)?)),
+        None => Err(division_by_zero()),
+    }
+}
+
+fn overflow_or_too_large(mode: Mode) -> EvalError {
+    match mode {
+        Mode::Big => too_large(),
+        _ => overflow(),
+    }
+}
+
+pub fn factorial(n: &Value, mode: Mode) -> Result<Value, EvalError> {
+    let n = match n.to_rational() {
+        Some(n) if n.is_integer() && !n.is_negative() => n,
+        _ => return Err(EvalError::new("階乗は 0 以上の整数のみ計算できます")),
+    };
+    let n = match n.to_i64() {
+        Some(n) => n,
+        None => return Err(overflow_or_too_large(mode)),
+    };
+
+    let mut result = Rational::from_i64(1);
+    for i in 2..=n {
+        result = check(result.mul(&Rational::from_i64(i)), mode)?;
+    }
+    Ok(Value::Exact(result))
+}
+
+fn integer(value: &Rational) -> Result<BigInt, EvalError> {
+    if value.is_integer() {
+        Ok(value.numerator().clone())
+    } else {
+        Err(EvalError::new("ビット演算は整数のみ対応しています"))
+    }
+}
+
+fn bitwise(
+    left: &Rational,
+    right: &Rational,
+    f: fn(&BigInt, &BigInt) -> BigInt,
+) -> Result<Rational, EvalError> {
+    Ok(Rational::from_integer(f(&integer(left)?, &integer(right)?)))
+}
+
+fn shift_amount(n: &Rational, mode: Mode) -> Result<u64, EvalError> {
+    let n = integer(n)?;
+    if n.is_negative() {
+        return Err(EvalError::new("シフト量は 0 以上の整数にしてください"));
+    }
+    match n.to_i64() {
+        Some(n) => Ok(n as u64),
+        None => Err(overflow_or_too_large(mode)),
+    }
+}
+
+fn binary_exact(
+    op: Operator,
+    left: &Rational,
+    right: &Rational,
+    mode: Mode,
+) -> Result<Value, EvalError> {
+    let value = match op {
+        Operator::Add => left.add(right),
+        Operator::Sub => left.sub(right),
+        Operator::Mul => left.mul(right),
+        Operator::Div => left.div(right).ok_or_else(division_by_zero)?,
+        Operator::Mod => left.rem(right).ok_or_else(division_by_zero)?,
+        Operator::Pow => return pow(left, right, mode),
+        Operator::BitAnd => bitwise(left, right, BigInt::and)?,
+        Operator::BitOr => bitwise(left, right, BigInt::or)?,
+        Operator::BitXor => bitwise(left, right, BigInt::xor)?,
+        Operator::Shl => {
+            let n = shift_amount(right, mode)?;
+            if n > MAX_BITS {
+                return Err(overflow_or_too_large(mode));
+            }
+            Rational::from_integer(integer(left)?.shl(n))
+        }
+        Operator::Shr => {
+            let left = integer(left)?;
+            // 桁数以上シフトすると 0 か -1 になる
+            let n = shift_amount(right, mode)?.min(left.bits() + 1);
+            Rational::from_integer(left.shr(n))
+        }
+    };
+    Ok(Value::Exact(check(value, mode)?))
+}
+
+fn binary_approx(op: Operator, left: f64, right: f64, mode: Mode) -> Result<Value, EvalError> {
+    // ビット演算は整数に戻して計算する
+    if op.is_bitwise() {
+        let left = Rational::from_f64(left).ok_or_else(not_real)?;
+        let right = Rational::from_f64(right).ok_or_else(not_real)?;
+        return binary_exact(op, &left, &right, mode);
+    }
+    if (op == Operator::Div || op == Operator::Mod) && right == 0.0 {
+        return Err(division_by_zero());
+    }
+    approx(match op {
+        Operator::Add => left + right,
+        Operator::Sub => left - right,
+        Operator::Mul => left * right,
+        Operator::Div => left / right,
+        Operator::Mod => left % right,
+        Operator::Pow => left.powf(right),
+        _ => unreachable!("ビット演算は整数に戻して計算している"),
+    })
+}
+
+pub fn binary(op: Operator, left: Value, right: Value, mode: Mode) -> Result<Value, EvalError> {
+    match (left, right) {
+        (Value::Exact(left), Value::Exact(right)) => binary_exact(op, &left, &right, mode),
+        (left, right) => binary_approx(op, left.to_f64(), right.to_f64(), mode),
+    }
+}
+
+fn binary_dimension(
+    op: Operator,
+    left: &Quantity,
+    right: &Quantity,
+) -> Result<Dimension, EvalError> {
+    match op {
+        Operator::Add | Operator::Sub | Operator::Mod => {
+            if left.dimension == right.dimension {
+                Ok(left.dimension)
+            } else {
+                Err(dimension_mismatch(&left.dimension, &right.dimension))
+            }
+        }
+        Operator::Mul => left
+            .dimension
+            .mul(&right.dimension)
+            .ok_or_else(dimension_too_large),
+        Operator::Div => left
+            .dimension
+            .div(&right.dimension)
+            .ok_or_else(dimension_too_large),
+        Operator::Pow => {
+            if !right.dimension.is_none() {
+                return Err(EvalError::new("指数に単位は付けられません"));
+            }
+            if left.dimension.is_none() {
+                return Ok(Dimension::none());
+            }
+            let exp = right
+                .value
+                .to_rational()
+                .filter(|exp| exp.is_integer())
+                .and_then(|exp| exp.to_i64())
+                .ok_or_else(|| EvalError::new("単位の付いた数の指数は整数のみ対応しています"))?;
+            left.dimension.pow(exp).ok_or_else(dimension_too_large)
+        }
+        _ => {
+            if left.dimension.is_none() && right.dimension.is_none() {
+                Ok(Dimension::none())
+            } else {
+                Err(EvalError::new(
+                    "ビット演算は単位の付いていない数のみ計算できます",
+                ))
+            }
+        }
+    }
+}
+
+// 変数で上書きされていない、0 の位置がずれている単位 (摂氏と華氏)
+fn offset_unit(expr: &Expr, env: &Environment) -> Option<Unit> {
+    match expr {
+        Expr::Variable(name, _) if !env.contains_key(name) => {
+            units::lookup(name).filter(|unit| unit.offset.is_some())
+        }
+        _ => None,
+    }
+}
+
+// 72F のような温度の値を基本単位にする
+fn absolute_temperature(value: Quantity, unit: Unit, mode: Mode) -> Result<Quantity, EvalError> {
+    let value = unitless(value, "温度の値")?;
+    let value = binary(Operator::Mul, value, Value::Exact(unit.factor), mode)?;
+    let value = match unit.offset {
+        Some(offset) => binary(Operator::Add, value, Value::Exact(offset), mode)?,
+        None => value,
+    };
+    Ok(Quantity::new(value, unit.dimension))
+}
+
+fn instant_only_error() -> EvalError {
+    EvalError::new("日時には時間の足し引きと、日時同士の引き算のみできます")
+}
+
+// 日時は足し引き以外の計算に使えない
+fn not_instant(quantity: Quantity) -> Result<Quantity, EvalError> {
+    if quantity.instant.is_some() {
+        Err(instant_only_error())
+    } else {
+        Ok(quantity)
+    }
+}
+
+// リストは集計関数の引数にしか使えない
+fn not_list(quantity: Quantity) -> Result<Quantity, EvalError> {
+    if quantity.items.is_some() {
+        Err(EvalError::new(
+            "リストは sum や mean などの集計関数の引数にのみ使えます",
+        ))
+    } else {
+        Ok(quantity)
+    }
+}
+
+fn scalar(quantity: Quantity) -> Result<Quantity, EvalError> {
+    not_instant(not_list(quantity)?)
+}
+
+// 日時と時間の足し引き、日時同士の引き算
+fn instant_binary(
+    op: Operator,
+    left: Quantity,
+    right: Quantity,
+    mode: Mode,
+) -> Result<Quantity, EvalError> {
+    let time = Dimension::time();
+    match (op, left.instant, right.instant) {
+        (Operator::Add, Some(time_zone), None) | (Operator::Sub, Some(time_zone), None)
+            if right.dimension == time =>
+        {
+            let value = binary(op, left.value, right.value, mode)?;
+            Ok(Quantity::instant(value, time_zone))
+        }
+        (Operator::Add, None, Some(time_zone)) if left.dimension == time => {
+            let value = binary(op, left.value, right.value, mode)?;
+            Ok(Quantity::instant(value, time_zone))
+        }
+        (Operator::Sub, Some(_), Some(_)) => {
+            let value = binary(op, left.value, right.value, mode)?;
+            Ok(Quantity::new(value, time))
+        }
+        _ => Err(instant_only_error()),
+    }
+}
+
+fn cast(quantity: Quantity, target: &str, column: usize) -> Result<Quantity, EvalError> {
+    match target {
+        // 秒数を日時にする。単位がなければ秒とみなす
+        "date" => {
+            if quantity.instant.is_some() {
+                return Ok(quantity);
+            }
+            if !quantity.dimension.is_none() && quantity.dimension != Dimension::time() {
+                return Err(EvalError::at("日時にできるのは秒数のみです", column));
+            }
+            Ok(Quantity::instant(quantity.value, TimeZone::jst()))
+        }
+        "unix" => match quantity.instant {
+            Some(_) => Ok(Quantity::number(quantity.value)),
+            None => Err(EvalError::at("UNIX 時間にできるのは日時のみです", column)),
+        },
+        _ => Err(cast_target_error(column)),
+    }
+}
+
+// 計算途中で panic や wraparound が起きないように、演算ごとに範囲を確かめる
+pub fn eval(expr: &Expr, ctx: &Context) -> Result<Quantity, EvalError> {
+    let mode = ctx.mode;
+    let env = ctx.env;
+    match expr {
+        Expr::Number(n) => Ok(Quantity::number(Value::Exact(check(n.clone(), mode)?))),
+        Expr::Unary(UnaryOperator::Plus, operand) => eval(operand, ctx),
+        Expr::Unary(UnaryOperator::Minus, operand) => {
+            let operand = scalar(eval(operand, ctx)?)?;
+            let value = check_value(operand.value.neg(), mode)?;
+            Ok(Quantity::new(value, operand.dimension))
+        }
+        Expr::Unary(UnaryOperator::BitNot, operand) => {
+            let operand = scalar(eval(operand, ctx)?)?;
+            let operand = unitless(operand, "ビット演算")?
+                .to_rational()
+                .ok_or_else(not_real)?;
+            let value = Rational::from_integer(integer(&operand)?.not());
+            Ok(Quantity::number(Value::Exact(check(value, mode)?)))
+        }
+        Expr::Factorial(operand) => {
+            let operand = unitless(scalar(eval(operand, ctx)?)?, "階乗")?;
+            Ok(Quantity::number(factorial(&operand, mode)?))
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let left = eval(lhs, ctx)?;
+            if *op == Operator::Mul {
+                if let Some(unit) = offset_unit(rhs, env) {
+                    return absolute_temperature(scalar(left)?, unit, mode);
+                }
+            }
+            let (left, right) = (not_list(left)?, not_list(eval(rhs, ctx)?)?);
+            if left.instant.is_some() || right.instant.is_some() {
+                return instant_binary(*op, left, right, mode);
+            }
+            let dimension = binary_dimension(*op, &left, &right)?;
+            let value = binary(*op, left.value, right.value, mode)?;
+            Ok(Quantity::new(value, dimension))
+        }
+        Expr::Variable(name, column) => {
+            if let Some(quantity) = env.get(name) {
+                return Ok(Quantity {
+                    value: check_value(quantity.value.clone(), mode)?,
+                    ..quantity.clone()
+                });
+            }
+            if let Some(value) = functions::constant(name) {
+                return Ok(Quantity::number(value));
+            }
+            match name.as_str() {
+                "now" => {
+                    let now = Rational::from_i64(ctx.now.timestamp());
+                    return Ok(Quantity::instant(Value::Exact(now), TimeZone::jst()));
+                }
+                "today" => {
+                    let today = datetime::start_of_day(ctx.now, &TimeZone::jst())
+                        .ok_or_else(|| EvalError::at("日時が範囲外です", *column))?;
+                    let today = Value::Exact(Rational::from_i64(today));
+                    return Ok(Quantity::instant(today, TimeZone::jst()));
+                }
+                _ => {}
+            }
+            if let Some(unit) = units::lookup(name) {
+                return Ok(Quantity::new(
+                    Value::Exact(check(unit.factor, mode)?),
+                    unit.dimension,
+                ));
+            }
+            if functions::is_function(name) {
+                return Err(EvalError::at(
+                    &format!(
+                        "{} は関数です。{}(...) の形で呼び出してください",
+                        name, name
+                    ),
+                    *column,
+                ));
+            }
+            Err(EvalError::at(
+                &format!("未定義の変数です: {}", name),
+                *column,
+            ))
+        }
+        Expr::Call(name, args, column) => {
+            // 集計関数に渡したリストは要素を引数として並べる
+            let mut values = Vec::new();
+            let mut dimensions = Vec::new();
+            for arg in args {
+                let arg = not_instant(eval(arg, ctx)?)?;
+                match arg.items {
+                    Some(items) if functions::accepts_list(name) => {
+                        dimensions.resize(dimensions.len() + items.len(), arg.dimension);
+                        values.extend(items);
+                    }
+                    Some(_) => {
+                        return Err(EvalError::at(
+                            &format!("{} の引数にリストは使えません", name),
+                            *column,
+                        ))
+                    }
+                    None => {
+                        dimensions.push(arg.dimension);
+                        values.push(arg.value);
+                    }
+                }
+            }
+            // 関数の中で起きたエラーは関数名の位置で示す
+            functions::result_dimension(name, &dimensions)
+                .and_then(|dimension| {
+                    if name == "range" {
+                        let items = functions::range(&values, mode)?;
+                        return Ok(Quantity::list(items, dimension));
+                    }
+                    let value = functions::call(name, &values, mode)?;
+                    Ok(Quantity::new(check_value(value, mode)?, dimension))
+                })
+                .map_err(|err| EvalError {
+                    column: err.column.or(Some(*column)),
+                    ..err
+                })
+        }
+        Expr::Convert(lhs, target, name) => {
+            let quantity = eval(lhs, ctx)?;
+            // 日時はタイムゾーンを変える
+            if quantity.instant.is_some() {
+                return match target.as_ref() {
+                    Expr::Variable(zone, column) => match TimeZone::find(zone) {
+                        Some(time_zone) => Ok(Quantity {
+                            instant: Some(time_zone),
+                            ..quantity
+                        }),
+                        None => Err(EvalError::at(
+                            &format!("タイムゾーンとして読めません: {}", zone),
+                            *column,
+                        )),
+                    },
+                    _ => Err(EvalError::new("日時はタイムゾーンにのみ変換できます")),
+                };
+            }
+            let (factor, dimension, offset) = match offset_unit(target, env) {
+                Some(unit) => (Value::Exact(unit.factor), unit.dimension, unit.offset),
+                None => {
+                    let target = scalar(eval(target, ctx)?)?;
+                    (target.value, target.dimension, None)
+                }
+            };
+            if quantity.dimension != dimension {
+                return Err(dimension_mismatch(&quantity.dimension, &dimension));
+            }
+            if factor.to_f64() == 0.0 {
+                return Err(division_by_zero());
+            }
+            Ok(Quantity {
+                unit: Some(DisplayUnit {
+                    name: name.clone(),
+                    factor,
+                    offset,
+                }),
+                ..quantity
+            })
+        }
+        Expr::DateTime(literal, column) => match datetime::parse_literal(literal) {
+            Some(timestamp) => Ok(Quantity::instant(
+                Value::Exact(Rational::from_i64(timestamp)),
+                TimeZone::jst(),
+            )),
+            None => Err(EvalError::at(
+                &format!("日時として読めません: {}", literal),
+                *column,
+            )),
+        },
+        Expr::Cast(operand, target, column) => {
+            cast(not_list(eval(operand, ctx)?)?, target, *column)
+        }
+        Expr::List(elements, column) => {
+            let mut items = Vec::with_capacity(elements.len());
+            let mut dimension = None;
+            for element in elements {
+                let element = eval(element, ctx)?;
+                if element.items.is_some() || element.instant.is_some() {
+                    return Err(EvalError::at(
+                        "リストの要素には数か単位の付いた値のみ使えます",
+                        *column,
+                    ));
+                }
+                let first = *dimension.get_or_insert(element.dimension);
+                if element.dimension != first {
+                    return Err(dimension_mismatch(&first, &element.dimension));
+                }
+                items.push(element.value);
+            }
+            Ok(Quantity::list(items, dimension.unwrap_or_default()))
+        }
+    }
+}
+
+pub fn execute(
+    statement: &Statement,
+    mode: Mode,
+    now: DateTime<Utc>,
+    env: &mut Environment,
+) -> Result<Quantity, EvalError> {
+    let (name, expr) = match statement {
+        Statement::Expr(expr) => (None, expr),
+        Statement::Assign(name, expr) => (Some(name), expr),
+    };
+    if let Some(name) = name {
+        if functions::is_reserved(name) {
+            return Err(EvalError::new(&format!(
+                "{} は組み込みの名前なので代入できません",
+                name
+            )));
+        }
+    }
+    let ctx = Context { mode, now, env };
+    let quantity = eval(expr, &ctx)?;
+    if let Some(name) = name {
+        env.insert(name.clone(), quantity.clone());
+    }
+    Ok(quantity)
+}
@@ -0,0 +1,409 @@
+use std::cmp::Ordering;
+use std::f64::consts;
+
+use super::evaluator::{
+    approx, binary, dimension_mismatch, division_by_zero, factorial, not_real, Mode, Value,
+};
+use super::number::{BigInt, Rational};
+use super::parser::Operator;
+use super::units::Dimension;
+use super::EvalError;
+
+const FUNCTIONS: [&str; 16] = [
+    "sqrt",
+    "abs",
+    "log",
+    "ln",
+    "sin",
+    "cos",
+    "min",
+    "max",
+    "round",
+    "factorial",
+    "sum",
+    "mean",
+    "median",
+    "stddev",
+    "percentile",
+    "range",
+];
+
+// リストを渡すと要素を引数として並べる関数
+const AGGREGATES: [&str; 7] = [
+    "min",
+    "max",
+    "sum",
+    "mean",
+    "median",
+    "stddev",
+    "percentile",
+];
+
+// range で作れるリストの長さの上限
+const MAX_LIST_LEN: i64 = 10_000;
+
+// これより大きい数の平方根は厳密に求めず近似する
+const EXACT_SQRT_MAX_BITS: u64 = 4096;
+
+pub fn constant(name: &str) -> Option<Value> {
+    match name {
+        "pi" => Some(Value::Approx(consts::PI)),
+        "e" => Some(Value::Approx(consts::E)),
+        _ => None,
+    }
+}
+
+pub fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+pub fn accepts_list(name: &str) -> bool {
+    AGGREGATES.contains(&name)
+}
+
+// 組み込みの関数と定数の名前、now と today、演算子の xor, in, as には代入できない
+pub fn is_reserved(name: &str) -> bool {
+    is_function(name)
+        || constant(name).is_some()
+        || ["now", "today", "xor", "in", "as"].contains(&name)
+}
+
+// 引数の単位から結果の単位を決める。abs, round, sqrt と集計関数以外は単位の付いていない数のみ
+pub fn result_dimension(name: &str, dimensions: &[Dimension]) -> Result<Dimension, EvalError> {
+    let first = dimensions.first().copied().unwrap_or_default();
+    match name {
+        "abs" | "min" | "max" | "sum" | "mean" | "median" | "stddev" | "range" => {
+            match dimensions.iter().find(|d| **d != first) {
+                Some(other) => Err(dimension_mismatch(&first, other)),
+                None => Ok(first),
+            }
+        }
+        // 最後の引数がパーセント
+        "percentile" => match dimensions.split_last() {
+            Some((p, _)) if !p.is_none() => {
+                Err(EvalError::new("percentile の p に単位は付けられません"))
+            }
+            Some((_, items)) => match items.iter().find(|d| **d != first) {
+                Some(other) => Err(dimension_mismatch(&first, other)),
+                None => Ok(first),
+            },
+            None => Ok(first),
+        },
+        "round" => {
+            if dimensions.iter().skip(1).all(|d| d.is_none()) {
+                Ok(first)
+            } else {
+                Err(EvalError::new("round の桁数に単位は付けられません"))
+            }
+        }
+        "sqrt" if dimensions.len() == 1 => first
+            .sqrt()
+            .ok_or_else(|| EvalError::new("単位の指数が奇数なので平方根をとれません")),
+        _ => {
+            if dimensions.iter().all(|d| d.is_none()) {
+                Ok(Dimension::none())
+            } else {
+                Err(EvalError::new(&format!(
+                    "{} の引数に単位は付けられません",
+                    name
+                )))
+            }
+        }
+    }
+}
+
+fn check_arity(name: &str, args: &[Value], min: usize, max: usize) -> Result<(), EvalError> {
+    if min <= args.len() && args.len() <= max {
+        return Ok(());
+    }
+    let expected = if min == max {
+        format!("{} 個", min)
+    } else if max == usize::MAX {
+        format!("{} 個以上", min)
+    } else {
+        format!("{} 個か {} 個", min, max)
+    };
+    Err(EvalError::new(&format!(
+        "{} の引数は {}です ({} 個渡されました)",
+        name,
+        expected,
+        args.len()
+    )))
+}
+
+fn sqrt(x: &Value) -> Result<Value, EvalError> {
+    if x.to_f64() < 0.0 {
+        return Err(not_real());
+    }
+    if let Value::Exact(n) = x {
+        if n.bits() <= EXACT_SQRT_MAX_BITS {
+            if let Some(root) = n.exact_sqrt() {
+                return Ok(Value::Exact(root));
+            }
+        }
+    }
+    approx(x.to_f64().sqrt())
+}
+
+fn abs(x: &Value) -> Value {
+    match x {
+        Value::Exact(n) if n.is_negative() => Value::Exact(n.neg()),
+        Value::Exact(n) => Value::Exact(n.clone()),
+        Value::Approx(x) => Value::Approx(x.abs()),
+    }
+}
+
+fn log_operand(x: &Value) -> Result<f64, EvalError> {
+    let x = x.to_f64();
+    if x <= 0.0 {
+        return Err(EvalError::new("対数は正の数のみ計算できます"));
+    }
+    Ok(x)
+}
+
+fn log(x: &Value, base: Option<&Value>) -> Result<Value, EvalError> {
+    let x = log_operand(x)?;
+    let base = match base {
+        None => return approx(x.log10()),
+        Some(base) => base.to_f64(),
+    };
+    if base <= 0.0 || base == 1.0 {
+        return Err(EvalError::new("対数の底は 1 以外の正の数にしてください"));
+    }
+    // 10 と 2 は専用の関数の方が誤差が出にくい
+    if base == 10.0 {
+        approx(x.log10())
+    } else if base == 2.0 {
+        approx(x.log2())
+    } else {
+        approx(x.ln() / base.ln())
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Exact(a), Value::Exact(b)) => a.cmp(b),
+        (a, b) => a
+            .to_f64()
+            .partial_cmp(&b.to_f64())
+            .unwrap_or(Ordering::Equal),
+    }
+}
+
+// 比較して wanted の側にある引数を選ぶ
+fn pick(args: &[Value], wanted: Ordering) -> Value {
+    let mut picked = &args[0];
+    for arg in &args[1..] {
+        if compare(arg, picked) == wanted {
+            picked = arg;
+        }
+    }
+    picked.clone()
+}
+
+fn sum(args: &[Value], mode: Mode) -> Result<Value, EvalError> {
+    let mut total = Value::Exact(Rational::from_i64(0));
+    for arg in args {
+        total = binary(Operator::Add, total, arg.clone(), mode)?;
+    }
+    Ok(total)
+}
+
+fn count(args: &[Value]) -> Value {
+    Value::Exact(Rational::from_i64(args.len() as i64))
+}
+
+fn mean(args: &[Value], mode: Mode) -> Result<Value, EvalError> {
+    binary(Operator::Div, sum(args, mode)?, count(args), mode)
+}
+
+fn sorted(args: &[Value]) -> Vec<Value> {
+    let mut sorted = args.to_vec();
+    sorted.sort_by(compare);
+    sorted
+}
+
+fn median(args: &[Value], mode: Mode) -> Result<Value, EvalError> {
+    let sorted = sorted(args);
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        return Ok(sorted[mid].clone());
+    }
+    mean(&sorted[mid - 1..=mid], mode)
+}
+
+// 標本標準偏差。n - 1 で割る
+fn stddev(args: &[Value], mode: Mode) -> Result<Value, EvalError> {
+    let mean = mean(args, mode)?;
+    let mut squares = Vec::with_capacity(args.len());
+    for arg in args {
+        let deviation = binary(Operator::Sub, arg.clone(), mean.clone(), mode)?;
+        squares.push(binary(Operator::Mul, deviation.clone(), deviation, mode)?);
+    }
+    let n = Value::Exact(Rational::from_i64(args.len() as i64 - 1));
+    let variance = binary(Operator::Div, sum(&squares, mode)?, n, mode)?;
+    sqrt(&variance)
+}
+
+// 両端を含む線形補間。Excel の PERCENTILE.INC と同じ
+fn percentile(items: &[Value], p: &Value, mode: Mode) -> Result<Value, EvalError> {
+    let p_f64 = p.to_f64();
+    if !(0.0..=100.0).contains(&p_f64) {
+        return Err(EvalError::new(
+            "percentile の p は 0 から 100 の間で指定してください",
+        ));
+    }
+    let sorted = sorted(items);
+    let last = Value::Exact(Rational::from_i64(sorted.len() as i64 - 1));
+    let hundred = Value::Exact(Rational::from_i64(100));
+    let rank = binary(
+        Operator::Mul,
+        binary(Operator::Div, p.clone(), hundred, mode)?,
+        last,
+        mode,
+    )?;
+    let rank = rank.to_rational().ok_or_else(not_real)?;
+    let lower = rank.trunc();
+    let index = lower.to_i64().ok_or_else(not_real)? as usize;
+    let low = sorted[index].clone();
+    let high = match sorted.get(index + 1) {
+        Some(high) => high.clone(),
+        None => return Ok(low),
+    };
+    let fraction = Value::Exact(rank.sub(&lower));
+    let gap = binary(Operator::Sub, high, low.clone(), mode)?;
+    binary(
+        Operator::Add,
+        low,
+        binary(Operator::Mul, fraction, gap, mode)?,
+        mode,
+    )
+}
+
+/// start から end までを step ずつ並べたリスト。end も含める
+pub fn range(args: &[Value], mode: Mode) -> Result<Vec<Value>, EvalError> {
+    check_arity("range", args, 2, 3)?;
+    let (start, end) = (&args[0], &args[1]);
+    let step = match args.get(2) {
+        Some(step) => step.clone(),
+        None => Value::Exact(Rational::from_i64(1)),
+    };
+    if step.to_f64() == 0.0 {
+        return Err(EvalError::new("range の刻み幅に 0 は使えません"));
+    }
+    let span = binary(Operator::Sub, end.clone(), start.clone(), mode)?;
+    let steps = binary(Operator::Div, span, step.clone(), mode)?;
+    if steps.to_f64() < 0.0 {
+        return Ok(Vec::new());
+    }
+    let steps = steps.to_rational().ok_or_else(division_by_zero)?.trunc();
+    let len = match steps.to_i64() {
+        Some(steps) if steps < MAX_LIST_LEN => steps + 1,
+        _ => {
+            return Err(EvalError::new(&format!(
+                "リストの要素は {} 個までです",
+                MAX_LIST_LEN
+            )))
+        }
+    };
+    (0..len)
+        .map(|i| {
+            let offset = binary(
+                Operator::Mul,
+                step.clone(),
+                Value::Exact(Rational::from_i64(i)),
+                mode,
+            )?;
+            binary(Operator::Add, start.clone(), offset, mode)
+        })
+        .collect()
+}
+
+// 0 から遠い方に丸める。digits を指定すると小数点以下その桁数で丸める
+fn round(x: &Value, digits: Option<&Value>) -> Result<Value, EvalError> {
+    let x = x.to_rational().ok_or_else(not_real)?;
+    let digits = match digits.map(|d| d.to_rational().and_then(|d| d.to_i64())) {
+        None => 0,
+        Some(Some(d)) if d.abs() <= 100 => d,
+        Some(_) => {
+            return Err(EvalError::new(
+                "桁数は -100 から 100 までの整数で指定してください",
+            ))
+        }
+    };
+    let scale = Rational::from_integer(BigInt::from_u64(10).pow(digits.unsigned_abs()));
+    let scale = if digits < 0 {
+        Rational::from_i64(1).div(&scale).ok_or_else(not_real)?
+    } else {
+        scale
+    };
+    let rounded = x.mul(&scale).round().div(&scale).ok_or_else(not_real)?;
+    Ok(Value::Exact(rounded))
+}
+
+pub fn call(name: &str, args: &[Value], mode: Mode) -> Result<Value, EvalError> {
+    match name {
+        "sqrt" => {
+            check_arity(name, args, 1, 1)?;
+            sqrt(&args[0])
+        }
+        "abs" => {
+            check_arity(name, args, 1, 1)?;
+            Ok(abs(&args[0]))
+        }
+        "log" => {
+            check_arity(name, args, 1, 2)?;
+            log(&args[0], args.get(1))
+        }
+        "ln" => {
+            check_arity(name, args, 1, 1)?;
+            approx(log_operand(&args[0])?.ln())
+        }
+        "sin" => {
+            check_arity(name, args, 1, 1)?;
+            approx(args[0].to_f64().sin())
+        }
+        "cos" => {
+            check_arity(name, args, 1, 1)?;
+            approx(args[0].to_f64().cos())
+        }
+        "min" => {
+            check_arity(name, args, 1, usize::MAX)?;
+            Ok(pick(args, Ordering::Less))
+        }
+        "max" => {
+            check_arity(name, args, 1, usize::MAX)?;
+            Ok(pick(args, Ordering::Greater))
+        }
+        "round" => {
+            check_arity(name, args, 1, 2)?;
+            round(&args[0], args.get(1))
+        }
+        "factorial" => {
+            check_arity(name, args, 1, 1)?;
+            factorial(&args[0], mode)
+        }
+        "sum" => {
+            check_arity(name, args, 1, usize::MAX)?;
+            sum(args, mode)
+        }
+        "mean" => {
+            check_arity(name, args, 1, usize::MAX)?;
+            mean(args, mode)
+        }
+        "median" => {
+            check_arity(name, args, 1, usize::MAX)?;
+            median(args, mode)
+        }
+        "stddev" => {
+            check_arity(name, args, 2, usize::MAX)?;
+            stddev(args, mode)
+        }
+        "percentile" => {
+            check_arity(name, args, 2, usize::MAX)?;
+            let (p, items) = args.split_last().unwrap();
+            percentile(items, p, mode)
+        }
+        _ => Err(EvalError::new(&format!("未定義の関数です: {}", name))),
+    }
+}
@@ -0,0 +1,1152 @@
+mod evaluator;
+mod functions;
+mod number;
+mod parser;
+mod tokenizer;
+mod trace;
+mod units;
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::{User, UserId};
+use serenity::prelude::{Context, TypeMapKey};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::utils::datetime;
+
+use evaluator::{execute, Context as EvalContext, Environment, Mode, Quantity, Value};
+use number::{BigInt, Rational};
+use parser::{parse_statement, Statement};
+use tokenizer::tokenize;
+use trace::Trace;
+use units::Dimension;
+
+// /eval で代入した変数。bot が動いている間だけユーザーごとに保持する
+// TypeMap 全体のロックを計算中に握らないよう、表は別のロックで守る
+pub struct Variables;
+
+impl TypeMapKey for Variables {
+    type Value = Arc<Mutex<HashMap<UserId, Environment>>>;
+}
+
+async fn variables(ctx: &Context) -> Arc<Mutex<HashMap<UserId, Environment>>> {
+    if let Some(variables) = ctx.data.read().await.get::<Variables>() {
+        return variables.clone();
+    }
+    let mut data = ctx.data.write().await;
+    data.entry::<Variables>().or_default().clone()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct EvalError {
+    message: String,
+    // 入力の何文字目か (1 始まり)
+    column: Option<usize>,
+}
+
+impl EvalError {
+    pub fn new(message: &str) -> Self {
+        EvalError {
+            message: message.to_string(),
+            column: None,
+        }
+    }
+
+    pub fn at(message: &str, column: usize) -> Self {
+        EvalError {
+            message: message.to_string(),
+            column: Some(column),
+        }
+    }
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "column {}: {}", column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+struct EvalOptions {
+    mode: Mode,
+    // 整数の結果を 2/8/16 進数とビット幅ごとの表現でも表示する
+    bases: bool,
+    // now と today の基準になる現在時刻
+    now: DateTime<Utc>,
+    // 式の読み方と計算の手順も表示する
+    trace: Option<Trace>,
+}
+
+// Discord は 3 秒以内に返事をしないと失敗扱いにする
+const EVAL_TIMEOUT: Duration = Duration::from_secs(2);
+// 計算するスレッドのスタック。読み込みも評価も途中経過の表示も式の深さだけ再帰し、
+// debug build では深さ MAX_DEPTH の式に 2 MiB (tokio の blocking スレッドの既定) では足りない
+const EVAL_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// 小数で表示するときの小数点以下の桁数
+const DECIMAL_DIGITS: usize = 20;
+
+fn format_value(value: &Value, mode: Mode) -> String {
+    match value {
+        Value::Exact(n) => format_rational(n, mode),
+        Value::Approx(x) => format_float(*x),
+    }
+}
+
+// 近似値は f64 の最短表記にする。極端に大きい数と小さい数は指数表記にする
+fn format_float(x: f64) -> String {
+    let abs = x.abs();
+    if abs != 0.0 && !(1e-6..1e16).contains(&abs) {
+        format!("{:e}", x)
+    } else {
+        format!("{}", x)
+    }
+}
+
+// 秒の端数は切り捨てて i64 に収まる整数にする
+fn whole_seconds(value: &Value) -> Option<i64> {
+    value.to_rational()?.trunc().to_i64()
+}
+
+// 単位の付いた値は後ろに単位を付ける。割り切れる値は分数にせず小数で表示する
+fn format_quantity(quantity: &Quantity, mode: Mode) -> Result<String, EvalError> {
+    if let Some(time_zone) = &quantity.instant {
+        return whole_seconds(&quantity.value)
+            .and_then(|timestamp| datetime::format(timestamp, time_zone))
+            .ok_or_else(|| EvalError::new("日時が範囲外です"));
+    }
+    // リストは要素ごとに単位を付けて並べる
+    if let Some(items) = &quantity.items {
+        let element = Quantity {
+            items: None,
+            ..quantity.clone()
+        };
+        let items = items
+            .iter()
+            .map(|item| {
+                let element = Quantity {
+                    value: item.clone(),
+                    ..element.clone()
+                };
+                format_quantity(&element, mode)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(format!("[{}]", items.join(", ")));
+    }
+    let value = quantity.value_in_unit(mode)?;
+    let unit = match &quantity.unit {
+        Some(unit) => unit.name.clone(),
+        None if quantity.dimension.is_none() => return Ok(format_value(&value, mode)),
+        None => quantity.dimension.to_string(),
+    };
+    let amount = match &value {
+        Value::Exact(n) => match n.to_decimal_string(DECIMAL_DIGITS) {
+            (decimal, true) => decimal,
+            _ => format_value(&value, mode),
+        },
+        Value::Approx(_) => format_value(&value, mode),
+    };
+    // 単位のない 1 分以上の整数秒は 3h 45min のような内訳も付ける
+    let seconds = match (&quantity.unit, value.to_rational()) {
+        (None, Some(n)) if quantity.dimension == Dimension::time() => n.to_i64(),
+        _ => None,
+    };
+    match seconds {
+        Some(seconds) if seconds.unsigned_abs() >= 60 => Ok(format!(
+            "{} {} ({})",
+            amount,
+            unit,
+            datetime::format_duration(seconds)
+        )),
+        _ => Ok(format!("{} {}", amount, unit)),
+    }
+}
+
+fn format_rational(value: &Rational, mode: Mode) -> String {
+    if value.is_integer() {
+        return value.to_fraction_string();
+    }
+    let (decimal, exact) = value.to_decimal_string(DECIMAL_DIGITS);
+    match (mode, exact) {
+        (Mode::Decimal, true) => decimal,
+        (Mode::Decimal, false) => format!("{}…", decimal),
+        (_, true) => format!("{} (= {})", value.to_fraction_string(), decimal),
+        (_, false) => format!("{} (≈ {})", value.to_fraction_string(), decimal),
+    }
+}
+
+// 2 進数は 4 桁ごとに _ で区切る
+fn group_binary(digits: &str) -> String {
+    let len = digits.len();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (len - i).is_multiple_of(4) {
+            grouped.push('_');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// 幅 bits の 2 の補数で表したときの 16 進数と 10 進数
+fn format_width(n: &BigInt, bits: u64, signed: bool) -> String {
+    let modulus = BigInt::one().shl(bits);
+    let unsigned = n.and(&modulus.sub(&BigInt::one()));
+    let half = BigInt::one().shl(bits - 1);
+    let (decimal, fits) = if signed {
+        let value = if unsigned >= half {
+            unsigned.sub(&modulus)
+        } else {
+            unsigned.clone()
+        };
+        let fits = half.neg() <= *n && *n < half;
+        (value, fits)
+    } else {
+        let fits = !n.is_negative() && *n < modulus;
+        (unsigned.clone(), fits)
+    };
+    let hex = format!(
+        "0x{:0>width$}",
+        unsigned.to_string_radix(16),
+        width = (bits / 4) as usize
+    );
+    if fits {
+        format!("{} ({})", hex, decimal)
+    } else {
+        format!("{} ({}) ※ 下位 {} bit のみ", hex, decimal, bits)
+    }
+}
+
+fn format_bases(value: &Value) -> String {
+    let n = match value.to_rational() {
+        Some(n) if n.is_integer() => n.numerator().clone(),
+        _ => return "(整数ではないため 2/8/16 進数では表示できません)".to_string(),
+    };
+    let sign = if n.is_negative() { "-" } else { "" };
+    let abs = n.abs();
+    let lines = [
+        format!("hex  {}0x{}", sign, abs.to_string_radix(16)),
+        format!("oct  {}0o{}", sign, abs.to_string_radix(8)),
+        format!("bin  {}0b{}", sign, group_binary(&abs.to_string_radix(2))),
+        format!("u8   {}", format_width(&n, 8, false)),
+        format!("u32  {}", format_width(&n, 32, false)),
+        format!("i64  {}", format_width(&n, 64, true)),
+    ];
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+fn safe_eval(s: String, options: &EvalOptions, env: &mut Environment) -> Result<String, EvalError> {
+    let end = s.chars().count() + 1;
+    let tokens = tokenize(&s)?;
+    let statement = parse_statement(tokens, end)?;
+    // 代入する前の変数で手順を追う
+    let steps = options.trace.map(|trace| {
+        let expr = match &statement {
+            Statement::Assign(_, expr) | Statement::Expr(expr) => expr,
+        };
+        let ctx = EvalContext {
+            mode: options.mode,
+            now: options.now,
+            env,
+        };
+        trace::render(expr, trace, &ctx)
+    });
+    let result = execute(&statement, options.mode, options.now, env)?;
+    let mut text = format_quantity(&result, options.mode)?;
+    if options.bases && result.items.is_some() {
+        text = format!("{}\n(リストは 2/8/16 進数では表示できません)", text);
+    } else if options.bases {
+        text = format!("{}\n{}", text, format_bases(&result.value));
+    }
+    if let Some(steps) = steps {
+        text = format!("{}\n```\n{}\n```", text, steps);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+fn eval(s: &str) -> Result<String, EvalError> {
+    eval_with(s, &EvalOptions::default())
+}
+
+#[cfg(test)]
+fn eval_with(s: &str, options: &EvalOptions) -> Result<String, EvalError> {
+    eval_in(s, options, &mut Environment::new())
+}
+
+#[cfg(test)]
+fn eval_in(s: &str, options: &EvalOptions, env: &mut Environment) -> Result<String, EvalError> {
+    safe_eval(s.to_string(), options, env)
+}
+
+#[test]
+fn test_safe_eval() {
+    assert_eq!(eval("2 + 2"), Ok("4".to_string()));
+    assert_eq!(eval("2 + 2 * 2"), Ok("6".to_string()));
+    assert_eq!(eval("(1 + 1) * 10 / 5"), Ok("4".to_string()));
+    assert_eq!(
+        eval("0+(1+(2+(3+(4+(5+(6+(7+8)))))))-(0+(1+(2+(3+(4+(5+(6+(7+8))))))))"),
+        Ok("0".to_string())
+    );
+    assert_eq!(eval("(2+1030/2)-2"), Ok("515".to_string()));
+    assert_eq!(eval("-3 + 1"), Ok("-2".to_string()));
+    assert_eq!(eval("2 * -1"), Ok("-2".to_string()));
+    assert_eq!(eval("2^3^2"), Ok("512".to_string()));
+    assert_eq!(eval("-2^2"), Ok("-4".to_string()));
+    assert_eq!(eval("(-2)^2"), Ok("4".to_string()));
+    assert_eq!(eval("--1"), Ok("1".to_string()));
+}
+
+#[test]
+fn test_safe_eval_errors() {
+    assert_eq!(
+        eval("(1 + 2"),
+        Err(EvalError::at("対応する ) がありません", 1))
+    );
+    assert_eq!(
+        eval("1 + 2)"),
+        Err(EvalError::at("対応する ( がありません", 6))
+    );
+    assert_eq!(
+        eval("1 + * 2"),
+        Err(EvalError::at("式が必要な位置に * があります", 5))
+    );
+    assert_eq!(
+        eval("1 +"),
+        Err(EvalError::at("式が途中で終わっています", 4))
+    );
+    assert_eq!(
+        eval("1 2"),
+        Err(EvalError::at("予期しないトークンです: 2", 3))
+    );
+    assert_eq!(
+        eval("1 + $"),
+        Err(EvalError::at("予期しない文字です: $", 5))
+    );
+    assert_eq!(
+        eval("()"),
+        Err(EvalError::at("式が必要な位置に ) があります", 2))
+    );
+}
+
+#[test]
+fn test_safe_eval_arithmetic_errors() {
+    let division_by_zero = Err(EvalError::new(
+        "0 で割ることはできません (division by zero)",
+    ));
+    let overflow = Err(evaluator::overflow());
+
+    assert_eq!(eval("1/0"), division_by_zero);
+    assert_eq!(eval("1%0"), division_by_zero);
+    assert_eq!(eval("1/(2-2)"), division_by_zero);
+    assert_eq!(eval("2^99"), overflow);
+    assert_eq!(eval("2^4294967296"), overflow);
+    assert_eq!(eval("9223372036854775807 + 1"), overflow);
+    assert_eq!(eval("-9223372036854775807 - 2"), overflow);
+    assert_eq!(eval("3037000500 * 3037000500"), overflow);
+    assert_eq!(eval("(-9223372036854775807 - 1) / -1"), overflow);
+    assert_eq!(eval("1 + 99999999999999999999"), overflow);
+    assert_eq!(eval("0^-1"), division_by_zero);
+
+    assert_eq!(eval("2^62"), Ok("4611686018427387904".to_string()));
+    assert_eq!(eval("-7 % 3"), Ok("-1".to_string()));
+}
+
+#[test]
+fn test_safe_eval_rational() {
+    assert_eq!(
+        eval("10/3"),
+        Ok("10/3 (≈ 3.33333333333333333333)".to_string())
+    );
+    assert_eq!(eval("1.5 * 2"), Ok("3".to_string()));
+    assert_eq!(eval("0.1 + 0.2"), Ok("3/10 (= 0.3)".to_string()));
+    assert_eq!(eval("2^-1"), Ok("1/2 (= 0.5)".to_string()));
+    assert_eq!(eval("-7/4"), Ok("-7/4 (= -1.75)".to_string()));
+    assert_eq!(eval("5!"), Ok("120".to_string()));
+    assert_eq!(eval("20!"), Ok("2432902008176640000".to_string()));
+    assert_eq!(eval("21!"), Err(evaluator::overflow()));
+    assert_eq!(eval("2^0.5"), Ok("1.4142135623730951".to_string()));
+    assert_eq!(
+        eval("(-1)!"),
+        Err(EvalError::new("階乗は 0 以上の整数のみ計算できます"))
+    );
+
+    let options = EvalOptions {
+        mode: Mode::Decimal,
+        ..EvalOptions::default()
+    };
+    assert_eq!(
+        eval_with("10/3", &options),
+        Ok("3.33333333333333333333…".to_string())
+    );
+    assert_eq!(eval_with("1/8", &options), Ok("0.125".to_string()));
+}
+
+#[test]
+fn test_safe_eval_big() {
+    let options = EvalOptions {
+        mode: Mode::Big,
+        ..EvalOptions::default()
+    };
+    assert_eq!(
+        eval_with("30!", &options),
+        Ok("265252859812191058636308480000000".to_string())
+    );
+    assert_eq!(
+        eval_with("2^200", &options),
+        Ok("1606938044258990275541962092341162602522202993782792835301376".to_string())
+    );
+    assert_eq!(
+        eval_with("99999999999999999999 + 1", &options),
+        Ok("100000000000000000000".to_string())
+    );
+    assert_eq!(
+        eval_with("1/3 + 2^70", &options),
+        Ok("3541774862152233910273/3 (≈ 1180591620717411303424.33333333333333333333)".to_string())
+    );
+    assert_eq!(
+        eval_with("(-1)^99999999999999999999", &options),
+        Ok("-1".to_string())
+    );
+    assert_eq!(
+        eval_with("2^1000000", &options),
+        Err(EvalError::new("計算結果が大きすぎます"))
+    );
+    assert_eq!(
+        eval_with("2^9000", &options),
+        Err(EvalError::new("計算結果が大きすぎます"))
+    );
+    // 上限に近い大きさの分数もすぐに約分できる
+    let mut env = Environment::new();
+    assert_eq!(
+        eval_in("2^8000 / 3^5000 * 3^5000", &options, &mut env),
+        eval_in("2^8000", &options, &mut env)
+    );
+}
+
+#[test]
+fn test_safe_eval_functions() {
+    assert_eq!(eval("sqrt(16)"), Ok("4".to_string()));
+    assert_eq!(eval("sqrt(2/8)"), Ok("1/2 (= 0.5)".to_string()));
+    assert_eq!(eval("sqrt(2)"), Ok("1.4142135623730951".to_string()));
+    assert_eq!(eval("abs(-7/2)"), Ok("7/2 (= 3.5)".to_string()));
+    assert_eq!(eval("log(1000)"), Ok("3".to_string()));
+    assert_eq!(eval("log(8, 2)"), Ok("3".to_string()));
+    assert_eq!(eval("ln(e)"), Ok("1".to_string()));
+    assert_eq!(eval("sin(0) + cos(0)"), Ok("1".to_string()));
+    assert_eq!(eval("min(3, 1/2, 2)"), Ok("1/2 (= 0.5)".to_string()));
+    assert_eq!(eval("max(1, pi, 3)"), Ok("3.141592653589793".to_string()));
+    assert_eq!(eval("round(2.5)"), Ok("3".to_string()));
+    assert_eq!(eval("round(pi, 2)"), Ok("157/50 (= 3.14)".to_string()));
+    assert_eq!(eval("factorial(5)"), Ok("120".to_string()));
+    assert_eq!(eval("2 * pi"), Ok("6.283185307179586".to_string()));
+
+    assert_eq!(
+        eval("sqrt(-1)"),
+        Err(EvalError::at("計算結果が実数になりません", 1))
+    );
+    assert_eq!(
+        eval("1 + log(0)"),
+        Err(EvalError::at("対数は正の数のみ計算できます", 5))
+    );
+    assert_eq!(
+        eval("sqrt(1, 2)"),
+        Err(EvalError::at(
+            "sqrt の引数は 1 個です (2 個渡されました)",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("max()"),
+        Err(EvalError::at(
+            "max の引数は 1 個以上です (0 個渡されました)",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("foo(1)"),
+        Err(EvalError::at("未定義の関数です: foo", 1))
+    );
+    assert_eq!(
+        eval("sqrt"),
+        Err(EvalError::at(
+            "sqrt は関数です。sqrt(...) の形で呼び出してください",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("max(1, 2"),
+        Err(EvalError::at("対応する ) がありません", 4))
+    );
+}
+
+#[test]
+fn test_safe_eval_variables() {
+    let options = EvalOptions::default();
+    let mut env = Environment::new();
+
+    assert_eq!(eval_in("x = 3", &options, &mut env), Ok("3".to_string()));
+    assert_eq!(eval_in("x * 2", &options, &mut env), Ok("6".to_string()));
+    assert_eq!(
+        eval_in("y = x / 2", &options, &mut env),
+        Ok("3/2 (= 1.5)".to_string())
+    );
+    assert_eq!(
+        eval_in("x = x + y", &options, &mut env),
+        Ok("9/2 (= 4.5)".to_string())
+    );
+    assert_eq!(
+        eval_in("z + 1", &options, &mut env),
+        Err(EvalError::at("未定義の変数です: z", 1))
+    );
+    assert_eq!(
+        eval_in("pi = 3", &options, &mut env),
+        Err(EvalError::new("pi は組み込みの名前なので代入できません"))
+    );
+    assert_eq!(
+        eval_in("x =", &options, &mut env),
+        Err(EvalError::at("式が途中で終わっています", 4))
+    );
+
+    // 失敗した代入で値は変わらない
+    assert_eq!(
+        eval_in("x = 1/0", &options, &mut env),
+        Err(evaluator::division_by_zero())
+    );
+    assert_eq!(
+        eval_in("x", &options, &mut env),
+        Ok("9/2 (= 4.5)".to_string())
+    );
+}
+
+#[test]
+fn test_safe_eval_bitwise() {
+    assert_eq!(eval("0xff"), Ok("255".to_string()));
+    assert_eq!(eval("0b1010_1010 + 0o17"), Ok("185".to_string()));
+    assert_eq!(eval("0xf0 & 0x3c"), Ok("48".to_string()));
+    assert_eq!(eval("0xf0 | 0x0f"), Ok("255".to_string()));
+    assert_eq!(eval("6 xor 3"), Ok("5".to_string()));
+    assert_eq!(eval("~0"), Ok("-1".to_string()));
+    assert_eq!(eval("~0 & 0xff"), Ok("255".to_string()));
+    assert_eq!(eval("1 << 4 + 1"), Ok("32".to_string()));
+    assert_eq!(eval("-17 >> 2"), Ok("-5".to_string()));
+    assert_eq!(eval("1 | 2 xor 3 & 6"), Ok("1".to_string()));
+    assert_eq!(eval("1 << 63"), Err(evaluator::overflow()));
+    assert_eq!(
+        eval("1.5 & 1"),
+        Err(EvalError::new("ビット演算は整数のみ対応しています"))
+    );
+    assert_eq!(
+        eval("1 << -1"),
+        Err(EvalError::new("シフト量は 0 以上の整数にしてください"))
+    );
+    assert_eq!(
+        eval("0x1g"),
+        Err(EvalError::at("数値として読めません: 0x1g", 1))
+    );
+    assert_eq!(
+        eval("xor = 1"),
+        Err(EvalError::new("xor は組み込みの名前なので代入できません"))
+    );
+
+    let options = EvalOptions {
+        bases: true,
+        ..EvalOptions::default()
+    };
+    assert_eq!(
+        eval_with("0xff", &options),
+        Ok("255
+```
+hex  0xff
+oct  0o377
+bin  0b1111_1111
+u8   0xff (255)
+u32  0x000000ff (255)
+i64  0x00000000000000ff (255)
+```"
+        .to_string())
+    );
+    assert_eq!(
+        eval_with("-2", &options),
+        Ok("-2
+```
+hex  -0x2
+oct  -0o2
+bin  -0b10
+u8   0xfe (254) ※ 下位 8 bit のみ
+u32  0xfffffffe (4294967294) ※ 下位 32 bit のみ
+i64  0xfffffffffffffffe (-2)
+```"
+        .to_string())
+    );
+    assert_eq!(
+        eval_with("1/2", &options),
+        Ok("1/2 (= 0.5)\n(整数ではないため 2/8/16 進数では表示できません)".to_string())
+    );
+}
+
+#[test]
+fn test_safe_eval_units() {
+    let options = EvalOptions::default();
+
+    assert_eq!(eval("1.5GiB in MB"), Ok("1610.612736 MB".to_string()));
+    assert_eq!(eval("3h + 45min"), Ok("13500 s (3h 45min)".to_string()));
+    assert_eq!(eval("1.5s + 30s"), Ok("31.5 s".to_string()));
+    assert_eq!(eval("3h + 45min in h"), Ok("3.75 h".to_string()));
+    assert_eq!(
+        eval("100km/h in m/s"),
+        Ok("250/9 (≈ 27.77777777777777777777) m/s".to_string())
+    );
+    assert_eq!(
+        eval("72F in C"),
+        Ok("200/9 (≈ 22.22222222222222222222) C".to_string())
+    );
+    assert_eq!(eval("-40F in C"), Ok("-40 C".to_string()));
+    assert_eq!(eval("100C in F"), Ok("212 F".to_string()));
+    assert_eq!(eval("0C in K"), Ok("273.15 K".to_string()));
+    assert_eq!(eval("2m * 3m"), Ok("6 m^2".to_string()));
+    assert_eq!(eval("10m / 4s"), Ok("2.5 m/s".to_string()));
+    assert_eq!(eval("sqrt(9m^2)"), Ok("3 m".to_string()));
+    assert_eq!(eval("max(1km, 900m) in km"), Ok("1 km".to_string()));
+    assert_eq!(eval("6ft + 1inch in cm"), Ok("185.42 cm".to_string()));
+    assert_eq!(
+        eval("1Gbit in MiB"),
+        Ok("119.20928955078125 MiB".to_string())
+    );
+    assert_eq!(eval("2h / 30min"), Ok("4".to_string()));
+    assert_eq!(eval("min(3, 1)"), Ok("1".to_string()));
+
+    assert_eq!(
+        eval("1m + 1s"),
+        Err(EvalError::new("単位の次元が合いません: m と s"))
+    );
+    assert_eq!(
+        eval("1kg in m"),
+        Err(EvalError::new("単位の次元が合いません: kg と m"))
+    );
+    assert_eq!(
+        eval("sin(1m)"),
+        Err(EvalError::at("sin の引数に単位は付けられません", 1))
+    );
+    assert_eq!(
+        eval("2^1m"),
+        Err(EvalError::new("指数に単位は付けられません"))
+    );
+    assert_eq!(
+        eval("1m & 1"),
+        Err(EvalError::new(
+            "ビット演算は単位の付いていない数のみ計算できます"
+        ))
+    );
+
+    // 変数は単位より優先される
+    let mut env = Environment::new();
+    assert_eq!(eval_in("m = 2", &options, &mut env), Ok("2".to_string()));
+    assert_eq!(eval_in("3m", &options, &mut env), Ok("6".to_string()));
+    assert_eq!(
+        eval_in("d = 1500mm in cm", &options, &mut env),
+        Ok("150 cm".to_string())
+    );
+    assert_eq!(eval_in("d", &options, &mut env), Ok("150 cm".to_string()));
+}
+
+#[test]
+fn test_safe_eval_datetime() {
+    // 2026-10-18 09:30:00 JST を現在時刻とする
+    let now = datetime::parse_literal("2026-10-18T09:30").unwrap();
+    let options = EvalOptions {
+        now: DateTime::from_timestamp(now, 0).unwrap(),
+        ..EvalOptions::default()
+    };
+    let eval = |s: &str| eval_with(s, &options);
+
+    assert_eq!(
+        eval("2026-10-18 + 45d"),
+        Ok("2026-12-02 (Wed) 00:00:00 JST".to_string())
+    );
+    assert_eq!(
+        eval("2026-12-24 - today"),
+        Ok("5788800 s (67d)".to_string())
+    );
+    assert_eq!(eval("(2026-12-24 - today) in d"), Ok("67 d".to_string()));
+    assert_eq!(
+        eval("now in UTC"),
+        Ok("2026-10-18 (Sun) 00:30:00 UTC".to_string())
+    );
+    assert_eq!(
+        eval("2026-10-18 09:30 + 3h in PST"),
+        Ok("2026-10-17 (Sat) 19:30:00 PST".to_string())
+    );
+    assert_eq!(
+        eval("now + 90min - now"),
+        Ok("5400 s (1h 30min)".to_string())
+    );
+    assert_eq!(
+        eval("1700000000 as date"),
+        Ok("2023-11-15 (Wed) 07:13:20 JST".to_string())
+    );
+    assert_eq!(
+        eval("2023-11-15 07:13:20 as unix"),
+        Ok("1700000000".to_string())
+    );
+    assert_eq!(
+        eval("2h as date in UTC"),
+        Ok("1970-01-01 (Thu) 02:00:00 UTC".to_string())
+    );
+
+    let instant_only = "日時には時間の足し引きと、日時同士の引き算のみできます";
+    assert_eq!(eval("now * 2"), Err(EvalError::new(instant_only)));
+    assert_eq!(eval("now + 1m"), Err(EvalError::new(instant_only)));
+    assert_eq!(eval("1d - now"), Err(EvalError::new(instant_only)));
+    assert_eq!(eval("sqrt(now)"), Err(EvalError::new(instant_only)));
+    assert_eq!(
+        eval("now in XST"),
+        Err(EvalError::at("タイムゾーンとして読めません: XST", 8))
+    );
+    assert_eq!(
+        eval("2026-02-30 + 1d"),
+        Err(EvalError::at("日時として読めません: 2026-02-30", 1))
+    );
+    assert_eq!(
+        eval("1m as date"),
+        Err(EvalError::at("日時にできるのは秒数のみです", 7))
+    );
+    assert_eq!(
+        eval("1 as unix"),
+        Err(EvalError::at("UNIX 時間にできるのは日時のみです", 6))
+    );
+    assert_eq!(
+        eval("1 as year"),
+        Err(EvalError::at(
+            "as の後には date か unix を指定してください",
+            6
+        ))
+    );
+    assert_eq!(
+        eval("now = 1"),
+        Err(EvalError::new("now は組み込みの名前なので代入できません"))
+    );
+    assert_eq!(
+        eval("10^15 as date"),
+        Err(EvalError::new("日時が範囲外です"))
+    );
+}
+
+#[test]
+fn test_safe_eval_lists() {
+    let options = EvalOptions::default();
+
+    assert_eq!(
+        eval("mean([3, 5, 8])"),
+        Ok("16/3 (≈ 5.33333333333333333333)".to_string())
+    );
+    assert_eq!(eval("mean(3, 5, 7)"), Ok("5".to_string()));
+    assert_eq!(eval("median([3, 1, 8])"), Ok("3".to_string()));
+    assert_eq!(eval("median([4, 1, 3, 2])"), Ok("5/2 (= 2.5)".to_string()));
+    assert_eq!(eval("sum([1, 2], 3, [4])"), Ok("10".to_string()));
+    assert_eq!(
+        eval("stddev([2, 4, 4, 4, 5, 5, 7, 9])"),
+        Ok("2.138089935299395".to_string())
+    );
+    assert_eq!(eval("stddev([1, 3])"), Ok("1.4142135623730951".to_string()));
+    assert_eq!(eval("stddev([1, 1, 1])"), Ok("0".to_string()));
+    assert_eq!(
+        eval("percentile([15, 20, 35, 40, 50], 40)"),
+        Ok("29".to_string())
+    );
+    assert_eq!(
+        eval("percentile(range(1, 100), 95)"),
+        Ok("1901/20 (= 95.05)".to_string())
+    );
+    assert_eq!(eval("percentile([7], 99)"), Ok("7".to_string()));
+    assert_eq!(eval("max(range(1, 10))"), Ok("10".to_string()));
+    assert_eq!(
+        eval("range(1, 10)"),
+        Ok("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]".to_string())
+    );
+    assert_eq!(
+        eval("range(0, 1, 0.25)"),
+        Ok("[0, 1/4 (= 0.25), 1/2 (= 0.5), 3/4 (= 0.75), 1]".to_string())
+    );
+    assert_eq!(eval("range(3, 1, -1)"), Ok("[3, 2, 1]".to_string()));
+    assert_eq!(eval("range(3, 1)"), Ok("[]".to_string()));
+    assert_eq!(eval("[1, 2 * 3]"), Ok("[1, 6]".to_string()));
+    assert_eq!(
+        eval("mean([120ms, 95ms, 310ms]) in ms"),
+        Ok("175 ms".to_string())
+    );
+    assert_eq!(eval("[1km, 500m] in km"), Ok("[1 km, 0.5 km]".to_string()));
+    assert_eq!(eval("percentile([1s, 3s], 50)"), Ok("2 s".to_string()));
+
+    let mut env = Environment::new();
+    assert_eq!(
+        eval_in("lat = [120, 95, 300, 80]", &options, &mut env),
+        Ok("[120, 95, 300, 80]".to_string())
+    );
+    assert_eq!(
+        eval_in("median(lat)", &options, &mut env),
+        Ok("215/2 (= 107.5)".to_string())
+    );
+
+    assert_eq!(
+        eval("[1, 2] + 1"),
+        Err(EvalError::new(
+            "リストは sum や mean などの集計関数の引数にのみ使えます"
+        ))
+    );
+    assert_eq!(
+        eval("sqrt([4])"),
+        Err(EvalError::at("sqrt の引数にリストは使えません", 1))
+    );
+    assert_eq!(
+        eval("[1m, 1s]"),
+        Err(EvalError::new("単位の次元が合いません: m と s"))
+    );
+    assert_eq!(
+        eval("[1, [2]]"),
+        Err(EvalError::at(
+            "リストの要素には数か単位の付いた値のみ使えます",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("mean([])"),
+        Err(EvalError::at(
+            "mean の引数は 1 個以上です (0 個渡されました)",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("stddev([1])"),
+        Err(EvalError::at(
+            "stddev の引数は 2 個以上です (1 個渡されました)",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("percentile([1, 2], 101)"),
+        Err(EvalError::at(
+            "percentile の p は 0 から 100 の間で指定してください",
+            1
+        ))
+    );
+    assert_eq!(
+        eval("range(1, 2, 0)"),
+        Err(EvalError::at("range の刻み幅に 0 は使えません", 1))
+    );
+    assert_eq!(
+        eval("range(1, 10^6)"),
+        Err(EvalError::at("リストの要素は 10000 個までです", 1))
+    );
+    assert_eq!(
+        eval("[1, 2"),
+        Err(EvalError::at("対応する ] がありません", 1))
+    );
+}
+
+#[test]
+fn test_safe_eval_trace() {
+    let options = EvalOptions {
+        trace: Some(Trace::Steps),
+        ..EvalOptions::default()
+    };
+    let mut env = Environment::new();
+    assert_eq!(
+        eval_in("x = 1 + 2 * 3", &options, &mut env),
+        Ok("7\n```\n1. 2 * 3 → 6\n2. 1 + 6 → 7\n```".to_string())
+    );
+    // 代入前の x で手順を追う
+    assert_eq!(
+        eval_in("x = x * x", &options, &mut env),
+        Ok("49\n```\n1. x * x → 49\n```".to_string())
+    );
+
+    let options = EvalOptions {
+        trace: Some(Trace::Tree),
+        bases: true,
+        ..EvalOptions::default()
+    };
+    let result = eval_in("0xF0 | 1", &options, &mut env).unwrap();
+    assert!(result.starts_with("241\n```\nhex  0xf1\n"));
+    assert!(result.ends_with("```\n```\n| → 241\n├─ 240\n└─ 1\n```"));
+
+    // 計算に失敗したときは手順を表示しない
+    assert_eq!(
+        eval_in("1 / (2 - 2)", &options, &mut env),
+        Err(evaluator::division_by_zero())
+    );
+}
+
+// Discord のメッセージは 2000 文字までなので、長すぎる数は途中を省略する
+fn abbreviate(value: String) -> String {
+    const KEEP: usize = 600;
+    let len = value.chars().count();
+    if len <= KEEP * 2 + 100 {
+        return value;
+    }
+    let head = value.chars().take(KEEP).collect::<String>();
+    let tail = value.chars().skip(len - KEEP).collect::<String>();
+    format!("{}…{} ({} 文字)", head, tail, len)
+}
+
+// 式の入力も含めて組み立てたメッセージが 2000 文字を超えたら末尾を切り、開いたままのコードブロックを閉じる
+fn fit_message(message: String) -> String {
+    const LIMIT: usize = 2000;
+    const CLOSE: &str = "\n```";
+    if message.chars().count() <= LIMIT {
+        return message;
+    }
+    let mut text = message
+        .chars()
+        .take(LIMIT - CLOSE.len() - 1)
+        .collect::<String>();
+    text.push('…');
+    if text.matches("```").count() % 2 == 1 {
+        text.push_str(CLOSE);
+    }
+    text
+}
+
+#[test]
+fn test_fit_message() {
+    assert_eq!(fit_message("1 + 1 = 2".to_string()), "1 + 1 = 2");
+
+    let message = format!("x = 1\n```\n{}\n```", "1. 1 + 1 → 2\n".repeat(300));
+    let fitted = fit_message(message);
+    assert_eq!(fitted.chars().count(), 2000);
+    assert!(fitted.ends_with("…\n```"));
+    assert_eq!(fitted.matches("```").count(), 2);
+
+    let message = format!("{} = 1", "1".repeat(2500));
+    let fitted = fit_message(message);
+    assert!(fitted.chars().count() <= 2000);
+    assert!(fitted.ends_with('…'));
+}
+
+// スタックを大きくしたスレッドで計算する。計算の途中で panic したら受け取る側はエラーになる
+fn spawn_eval(
+    input: String,
+    options: EvalOptions,
+    mut env: Environment,
+) -> io::Result<oneshot::Receiver<(Result<String, EvalError>, Environment)>> {
+    let (sender, receiver) = oneshot::channel();
+    thread::Builder::new()
+        .name("eval".to_string())
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || {
+            let result = safe_eval(input, &options, &mut env);
+            let _ = sender.send((result, env));
+        })?;
+    Ok(receiver)
+}
+
+#[test]
+fn test_spawn_eval_deep() {
+    let eval = |input: String, trace: Option<Trace>| {
+        let options = EvalOptions {
+            trace,
+            ..EvalOptions::default()
+        };
+        spawn_eval(input, options, Environment::new())
+            .unwrap()
+            .blocking_recv()
+            .unwrap()
+            .0
+    };
+    // 上限ちょうどの深さの式でもスタックを使い切らない
+    let terms = parser::MAX_DEPTH - 1;
+    for trace in [None, Some(Trace::Tree), Some(Trace::Steps)] {
+        let sum = vec!["1"; terms].join("+");
+        assert!(eval(sum, trace).is_ok());
+        let nested = format!("{}1{}", "(".repeat(terms), ")".repeat(terms));
+        assert!(eval(nested, trace).is_ok());
+        let negated = format!("{}1", "-".repeat(terms));
+        assert!(eval(negated, trace).is_ok());
+    }
+    assert!(eval(vec!["1"; terms + 1].join("+"), None).is_err());
+    assert!(eval(
+        format!("{}1{}", "(".repeat(terms + 1), ")".repeat(terms + 1)),
+        None
+    )
+    .is_err());
+}
+
+// コードブロックでの文字の幅。日本語などの全角の文字は半角 2 文字分になる
+fn display_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+// エラーの位置を ^ で示す。column は文字数なので、前の文字の幅だけ空ける
+fn render_error(input: &str, err: &EvalError) -> String {
+    match err.column {
+        Some(column) => {
+            let width = input.chars().take(column - 1).map(display_width).sum();
+            format!(
+                "```\n{}\n{}^ {}\n```",
+                input,
+                " ".repeat(width),
+                err.message
+            )
+        }
+        None => err.message.clone(),
+    }
+}
+
+#[test]
+fn test_render_error() {
+    let err = EvalError::at("予期しないトークンです: $", 5);
+    assert_eq!(
+        render_error("1 + $", &err),
+        "```\n1 + $\n    ^ 予期しないトークンです: $\n```"
+    );
+    let err = EvalError::at("予期しないトークンです: $", 7);
+    assert_eq!(
+        render_error("日本語 + $", &err),
+        "```\n日本語 + $\n         ^ 予期しないトークンです: $\n```"
+    );
+    assert_eq!(
+        render_error("1 +", &EvalError::new("式がありません")),
+        "式がありません"
+    );
+}
+
+pub async fn run(options: &[CommandDataOption], ctx: &Context, user: &User) -> String {
+    let eval_target = match options.iter().find(|option| option.name == "eval") {
+        Some(option) => match &option.resolved {
+            Some(value) => match value {
+                CommandDataOptionValue::String(eval_target) => eval_target,
+                _ => return "計算式を入力してください".to_string(),
+            },
+            None => return "計算式を入力してください".to_string(),
+        },
+        None => return "計算式を入力してください".to_string(),
+    };
+
+    let mode = match options.iter().find(|option| option.name == "mode") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match Mode::from_name(name) {
+                Some(mode) => mode,
+                None => return "モードが不正です".to_string(),
+            },
+            _ => return "モードが不正です".to_string(),
+        },
+        None => Mode::default(),
+    };
+
+    let trace = match options.iter().find(|option| option.name == "trace") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match Trace::from_name(name) {
+                Some(trace) => Some(trace),
+                None => return "表示方法が不正です".to_string(),
+            },
+            _ => return "表示方法が不正です".to_string(),
+        },
+        None => None,
+    };
+
+    let bases = match options.iter().find(|option| option.name == "bases") {
+        Some(option) => matches!(option.resolved, Some(CommandDataOptionValue::Boolean(true))),
+        None => false,
+    };
+
+    // 計算中はロックを握らず、写しで計算してから書き戻す
+    let variables = variables(ctx).await;
+    let env = variables
+        .lock()
+        .await
+        .get(&user.id)
+        .cloned()
+        .unwrap_or_default();
+    let options = EvalOptions {
+        mode,
+        bases,
+        now: Utc::now(),
+        trace,
+    };
+    let input = eval_target.to_string();
+    // 重い計算が非同期のワーカーを止めないよう別のスレッドで計算し、時間がかかりすぎたら諦める
+    let result = match spawn_eval(input, options, env) {
+        Ok(task) => match tokio::time::timeout(EVAL_TIMEOUT, task).await {
+            Ok(Ok((result, env))) => {
+                if result.is_ok() {
+                    variables.lock().await.insert(user.id, env);
+                }
+                result
+            }
+            Ok(Err(_)) => Err(EvalError::new("計算中にエラーが発生しました")),
+            Err(_) => Err(EvalError::new("計算に時間がかかりすぎたため中断しました")),
+        },
+        Err(_) => Err(EvalError::new("計算を始められませんでした")),
+    };
+
+    let message = match result {
+        Ok(result) => format!("{} = {}", eval_target, abbreviate(result)),
+        Err(err) => render_error(eval_target, &err),
+    };
+    fit_message(message)
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("eval")
+        .description("演算します")
+        .create_option(|option| {
+            option
+                .name("eval")
+                .description("計算式。x = 1 + 2 のように代入した変数は後から使えます")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("mode")
+                .description("計算モード (既定は rational)")
+                .kind(CommandOptionType::String)
+                .add_string_choice("rational: 分数と小数で表示", "rational")
+                .add_string_choice("decimal: 小数で表示", "decimal")
+                .add_string_choice("big: 任意精度", "big")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("bases")
+                .description("2/8/16 進数と u8/u32/i64 の 2 の補数表現でも表示します")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("trace")
+                .description("式の読み方と計算の手順も表示します")
+                .kind(CommandOptionType::String)
+                .add_string_choice("tree: 構文木", "tree")
+                .add_string_choice("steps: 計算の手順", "steps")
+                .required(false)
+        })
+}
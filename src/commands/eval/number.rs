@@ -0,0 +1,1002 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// 任意精度の整数と有理数
+// 桁は 2^32 進数で下の桁から並べる。0 は magnitude が空で negative は false
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+fn trim(mut v: Vec<u32>) -> Vec<u32> {
+    while v.last() == Some(&0) {
+        v.pop();
+    }
+    v
+}
+
+fn mag_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        if x != y {
+            return x.cmp(y);
+        }
+    }
+    Ordering::Equal
+}
+
+fn mag_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &x) in long.iter().enumerate() {
+        let sum = x as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        out.push(carry as u32);
+    }
+    out
+}
+
+// a >= b であること
+fn mag_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &x) in a.iter().enumerate() {
+        let mut diff = x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        out.push(diff as u32);
+    }
+    trim(out)
+}
+
+fn mag_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u64 * y as u64 + out[i + j] as u64 + carry;
+            out[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(out)
+}
+
+fn mag_divrem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut out = vec![0u32; a.len()];
+    let mut rem = 0u64;
+    for i in (0..a.len()).rev() {
+        let cur = (rem << 32) | a[i] as u64;
+        out[i] = (cur / d as u64) as u32;
+        rem = cur % d as u64;
+    }
+    (trim(out), rem as u32)
+}
+
+fn mag_bits(a: &[u32]) -> u64 {
+    match a.last() {
+        Some(&top) => (a.len() as u64 - 1) * 32 + (32 - top.leading_zeros() as u64),
+        None => 0,
+    }
+}
+
+fn mag_trailing_zeros(a: &[u32]) -> u64 {
+    match a.iter().position(|&limb| limb != 0) {
+        Some(i) => i as u64 * 32 + a[i].trailing_zeros() as u64,
+        None => 0,
+    }
+}
+
+fn mag_shr(a: &[u32], n: u64) -> Vec<u32> {
+    let limbs = (n / 32) as usize;
+    let bits = (n % 32) as u32;
+    if limbs >= a.len() {
+        return Vec::new();
+    }
+    let rest = &a[limbs..];
+    if bits == 0 {
+        return rest.to_vec();
+    }
+    let mut out = Vec::with_capacity(rest.len());
+    for (i, &limb) in rest.iter().enumerate() {
+        let high = rest.get(i + 1).map_or(0, |&h| h << (32 - bits));
+        out.push((limb >> bits) | high);
+    }
+    trim(out)
+}
+
+fn mag_shl(a: &[u32], n: u64) -> Vec<u32> {
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let limbs = (n / 32) as usize;
+    let bits = (n % 32) as u32;
+    let mut out = vec![0u32; limbs];
+    let mut carry = 0u32;
+    for &limb in a {
+        if bits == 0 {
+            out.push(limb);
+        } else {
+            out.push((limb << bits) | carry);
+            carry = limb >> (32 - bits);
+        }
+    }
+    if carry > 0 {
+        out.push(carry);
+    }
+    out
+}
+
+// Knuth の Algorithm D。筆算と同じ要領で、商を 1 limb ずつ上位 2 limb から見積もって割っていく
+fn mag_divrem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if mag_cmp(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let (q, r) = mag_divrem_small(a, b[0]);
+        return (q, trim(vec![r]));
+    }
+
+    // 割る数の最上位 bit が立つようにずらしておくと、見積もった商は多くても 2 しか大きくならない
+    let shift = b[b.len() - 1].leading_zeros() as u64;
+    let v = mag_shl(b, shift);
+    let mut u = mag_shl(a, shift);
+    u.resize(a.len() + 1, 0);
+    let n = v.len();
+    let base = 1u64 << 32;
+
+    let mut q = vec![0u32; a.len() - n + 1];
+    for j in (0..q.len()).rev() {
+        let top = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut qhat = top / v[n - 1] as u64;
+        let mut rhat = top % v[n - 1] as u64;
+        while qhat >= base || qhat * v[n - 2] as u64 > ((rhat << 32) | u[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += v[n - 1] as u64;
+            if rhat >= base {
+                break;
+            }
+        }
+
+        // u の j 桁目から qhat * v を引く
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let p = qhat * v[i] as u64 + carry;
+            carry = p >> 32;
+            let t = u[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+            u[i + j] = t as u32;
+            borrow = (t < 0) as i64;
+        }
+        let t = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = t as u32;
+
+        // 引きすぎたときは 1 回分足し戻す
+        if t < 0 {
+            qhat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        q[j] = qhat as u32;
+    }
+    (trim(q), trim(mag_shr(&u[..n], shift)))
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt {
+            negative: false,
+            magnitude: Vec::new(),
+        }
+    }
+
+    pub fn one() -> Self {
+        BigInt::from_u64(1)
+    }
+
+    fn from_parts(negative: bool, magnitude: Vec<u32>) -> Self {
+        let magnitude = trim(magnitude);
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn from_u64(n: u64) -> Self {
+        BigInt::from_parts(false, vec![n as u32, (n >> 32) as u32])
+    }
+
+    pub fn from_i64(n: i64) -> Self {
+        let mut value = BigInt::from_u64(n.unsigned_abs());
+        value.negative = n < 0;
+        value
+    }
+
+    /// 10 進数の数字列を読む。符号は受け付けない
+    pub fn parse_decimal(digits: &str) -> Option<Self> {
+        BigInt::parse_radix(digits, 10)
+    }
+
+    /// radix 進数の数字列を読む。符号は受け付けない
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude: Vec<u32> = Vec::new();
+        for c in digits.chars() {
+            let d = c.to_digit(radix)?;
+            let mut carry = d as u64;
+            for limb in magnitude.iter_mut() {
+                let t = *limb as u64 * radix as u64 + carry;
+                *limb = t as u32;
+                carry = t >> 32;
+            }
+            if carry > 0 {
+                magnitude.push(carry as u32);
+            }
+        }
+        Some(BigInt::from_parts(false, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        BigInt::from_parts(false, self.magnitude.clone())
+    }
+
+    pub fn bits(&self) -> u64 {
+        mag_bits(&self.magnitude)
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let mut value = 0u64;
+        for (i, &limb) in self.magnitude.iter().enumerate() {
+            value |= (limb as u64) << (32 * i);
+        }
+        if self.negative {
+            if value <= i64::MAX as u64 + 1 {
+                Some((value as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else if value <= i64::MAX as u64 {
+            Some(value as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &BigInt) -> Self {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, mag_add(&self.magnitude, &other.magnitude));
+        }
+        match mag_cmp(&self.magnitude, &other.magnitude) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, mag_sub(&other.magnitude, &self.magnitude))
+            }
+            _ => BigInt::from_parts(self.negative, mag_sub(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> Self {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mag_mul(&self.magnitude, &other.magnitude),
+        )
+    }
+
+    /// 0 方向に切り捨てた商と余りを返す。余りの符号は self と同じ
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (q, r) = mag_divrem(&self.magnitude, &other.magnitude);
+        Some((
+            BigInt::from_parts(self.negative != other.negative, q),
+            BigInt::from_parts(self.negative, r),
+        ))
+    }
+
+    pub fn pow(&self, mut exp: u64) -> Self {
+        let mut base = self.clone();
+        let mut result = BigInt::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    // 長さ len の 2 の補数表現。len は値が収まる長さであること
+    fn to_twos_complement(&self, len: usize) -> Vec<u32> {
+        let mut limbs = self.magnitude.clone();
+        limbs.resize(len, 0);
+        if !self.negative {
+            return limbs;
+        }
+        // 反転して 1 を足す
+        let mut carry = 1u64;
+        for limb in limbs.iter_mut() {
+            let t = (!*limb) as u64 + carry;
+            *limb = t as u32;
+            carry = t >> 32;
+        }
+        limbs
+    }
+
+    fn from_twos_complement(limbs: Vec<u32>) -> Self {
+        let negative = matches!(limbs.last(), Some(&top) if top >> 31 == 1);
+        if !negative {
+            return BigInt::from_parts(false, limbs);
+        }
+        let value = BigInt::from_parts(false, limbs.iter().map(|limb| !limb).collect());
+        value.add(&BigInt::one()).neg()
+    }
+
+    // 負の数は無限に 1 が続く 2 の補数として扱う
+    fn bitwise(&self, other: &BigInt, f: impl Fn(u32, u32) -> u32) -> Self {
+        let len = self.magnitude.len().max(other.magnitude.len()) + 1;
+        let a = self.to_twos_complement(len);
+        let b = other.to_twos_complement(len);
+        BigInt::from_twos_complement(a.iter().zip(b.iter()).map(|(&x, &y)| f(x, y)).collect())
+    }
+
+    pub fn and(&self, other: &BigInt) -> Self {
+        self.bitwise(other, |x, y| x & y)
+    }
+
+    pub fn or(&self, other: &BigInt) -> Self {
+        self.bitwise(other, |x, y| x | y)
+    }
+
+    pub fn xor(&self, other: &BigInt) -> Self {
+        self.bitwise(other, |x, y| x ^ y)
+    }
+
+    /// -x - 1
+    pub fn not(&self) -> Self {
+        self.neg().sub(&BigInt::one())
+    }
+
+    pub fn shl(&self, n: u64) -> Self {
+        BigInt::from_parts(self.negative, mag_shl(&self.magnitude, n))
+    }
+
+    /// 負の無限大方向に丸める算術シフト
+    pub fn shr(&self, n: u64) -> Self {
+        if !self.negative {
+            return BigInt::from_parts(false, mag_shr(&self.magnitude, n));
+        }
+        // -((|x| - 1) >> n) - 1
+        let magnitude = self.abs().sub(&BigInt::one());
+        BigInt::from_parts(false, mag_shr(&magnitude.magnitude, n)).not()
+    }
+
+    /// radix 進数の文字列。radix は 2 から 36 まで
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut digits = Vec::new();
+        let mut rest = self.magnitude.clone();
+        while !rest.is_empty() {
+            let (q, r) = mag_divrem_small(&rest, radix);
+            digits.push(std::char::from_digit(r, radix).unwrap_or('?'));
+            rest = q;
+        }
+        if self.negative {
+            digits.push('-');
+        }
+        digits.iter().rev().collect()
+    }
+
+    /// 上位 64 bit を f64 にしたものと、残りの bit 数の組。値は mantissa * 2^shift
+    fn to_f64_parts(&self) -> (f64, u64) {
+        let shift = self.bits().saturating_sub(64);
+        let top = mag_shr(&self.magnitude, shift);
+        let mut value = 0u64;
+        for (i, &limb) in top.iter().enumerate() {
+            value |= (limb as u64) << (32 * i);
+        }
+        let mantissa = if self.negative {
+            -(value as f64)
+        } else {
+            value as f64
+        };
+        (mantissa, shift)
+    }
+
+    /// 0 以上の整数の平方根の整数部分。負の数は None
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.negative {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(BigInt::zero());
+        }
+        // ニュートン法。初期値は平方根以上にしておくと単調に減っていく
+        let two = BigInt::from_u64(2);
+        let mut x = two.pow(self.bits().div_ceil(2));
+        loop {
+            let (q, _) = self.div_rem(&x)?;
+            let (y, _) = x.add(&q).div_rem(&two)?;
+            if y >= x {
+                return Some(x);
+            }
+            x = y;
+        }
+    }
+
+    // 二進 GCD。割り算を使わず、2 で割るのと引き算だけで求める
+    pub fn gcd(&self, other: &BigInt) -> Self {
+        if self.is_zero() {
+            return other.abs();
+        }
+        if other.is_zero() {
+            return self.abs();
+        }
+        let (za, zb) = (
+            mag_trailing_zeros(&self.magnitude),
+            mag_trailing_zeros(&other.magnitude),
+        );
+        let mut a = mag_shr(&self.magnitude, za);
+        let mut b = mag_shr(&other.magnitude, zb);
+        // a と b はどちらも奇数なので、差は偶数になる
+        loop {
+            match mag_cmp(&a, &b) {
+                Ordering::Equal => break,
+                Ordering::Less => std::mem::swap(&mut a, &mut b),
+                Ordering::Greater => {}
+            }
+            a = mag_sub(&a, &b);
+            a = mag_shr(&a, mag_trailing_zeros(&a));
+        }
+        BigInt::from_parts(false, mag_shl(&a, za.min(zb)))
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => mag_cmp(&self.magnitude, &other.magnitude),
+            (true, true) => mag_cmp(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // 10^9 ずつ割って下の桁から作る
+        let mut chunks = Vec::new();
+        let mut rest = self.magnitude.clone();
+        while !rest.is_empty() {
+            let (q, r) = mag_divrem_small(&rest, 1_000_000_000);
+            chunks.push(r);
+            rest = q;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap_or(0))?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+// 常に既約で、分母は正
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+impl Rational {
+    pub fn new(num: BigInt, den: BigInt) -> Option<Self> {
+        if den.is_zero() {
+            return None;
+        }
+        let (num, den) = if den.is_negative() {
+            (num.neg(), den.neg())
+        } else {
+            (num, den)
+        };
+        let g = num.gcd(&den);
+        if g == BigInt::one() || g.is_zero() {
+            return Some(Rational { num, den });
+        }
+        let (num, _) = num.div_rem(&g)?;
+        let (den, _) = den.div_rem(&g)?;
+        Some(Rational { num, den })
+    }
+
+    pub fn from_integer(n: BigInt) -> Self {
+        Rational {
+            num: n,
+            den: BigInt::one(),
+        }
+    }
+
+    pub fn from_i64(n: i64) -> Self {
+        Rational::from_integer(BigInt::from_i64(n))
+    }
+
+    /// `123` や `1.25` のような 10 進数のリテラルを読む
+    pub fn parse_decimal(literal: &str) -> Option<Self> {
+        let (int_part, frac_part) = match literal.find('.') {
+            Some(pos) => (&literal[..pos], &literal[pos + 1..]),
+            None => (literal, ""),
+        };
+        let digits = format!("{}{}", int_part, frac_part);
+        let num = BigInt::parse_decimal(&digits)?;
+        let den = BigInt::from_u64(10).pow(frac_part.len() as u64);
+        Rational::new(num, den)
+    }
+
+    /// 有限の f64 は 2 進の有理数なので誤差なく変換できる。NaN と無限大は None
+    pub fn from_f64(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        if x == 0.0 {
+            return Some(Rational::from_i64(0));
+        }
+        let bits = x.to_bits();
+        let negative = bits >> 63 == 1;
+        let exponent = ((bits >> 52) & 0x7ff) as i64;
+        let fraction = bits & ((1 << 52) - 1);
+        // 非正規化数は暗黙の 1 を持たない
+        let (mantissa, exponent) = if exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), exponent - 1075)
+        };
+        let mut num = BigInt::from_u64(mantissa);
+        if negative {
+            num = num.neg();
+        }
+        let power = BigInt::from_u64(2).pow(exponent.unsigned_abs());
+        if exponent >= 0 {
+            Some(Rational::from_integer(num.mul(&power)))
+        } else {
+            Rational::new(num, power)
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let (num, num_shift) = self.num.to_f64_parts();
+        let (den, den_shift) = self.den.to_f64_parts();
+        let shift = (num_shift as i64 - den_shift as i64).clamp(i32::MIN as i64, i32::MAX as i64);
+        num / den * 2f64.powi(shift as i32)
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == BigInt::one()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num.is_negative()
+    }
+
+    pub fn neg(&self) -> Self {
+        Rational {
+            num: self.num.neg(),
+            den: self.den.clone(),
+        }
+    }
+
+    pub fn add(&self, other: &Rational) -> Self {
+        let num = self.num.mul(&other.den).add(&other.num.mul(&self.den));
+        let den = self.den.mul(&other.den);
+        // 分母は 0 にならない
+        Rational::new(num, den).unwrap_or_else(|| Rational::from_i64(0))
+    }
+
+    pub fn sub(&self, other: &Rational) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Rational) -> Self {
+        let num = self.num.mul(&other.num);
+        let den = self.den.mul(&other.den);
+        Rational::new(num, den).unwrap_or_else(|| Rational::from_i64(0))
+    }
+
+    /// 0 で割ったときは None
+    pub fn div(&self, other: &Rational) -> Option<Self> {
+        Rational::new(self.num.mul(&other.den), self.den.mul(&other.num))
+    }
+
+    /// 商を 0 方向に切り捨てたときの余り。符号は self と同じ
+    pub fn rem(&self, other: &Rational) -> Option<Self> {
+        let quotient = self.div(other)?.trunc();
+        Some(self.sub(&other.mul(&quotient)))
+    }
+
+    pub fn trunc(&self) -> Self {
+        // 分母は 0 にならない
+        let (q, _) = self
+            .num
+            .div_rem(&self.den)
+            .unwrap_or_else(|| (BigInt::zero(), BigInt::zero()));
+        Rational::from_integer(q)
+    }
+
+    /// 0 から遠い方に丸めた整数
+    pub fn round(&self) -> Self {
+        let half = Rational {
+            num: BigInt::one(),
+            den: BigInt::from_u64(2),
+        };
+        if self.is_negative() {
+            self.sub(&half).trunc()
+        } else {
+            self.add(&half).trunc()
+        }
+    }
+
+    /// 分子と分母がどちらも平方数のときだけ平方根を返す
+    pub fn exact_sqrt(&self) -> Option<Self> {
+        let num = self.num.sqrt()?;
+        let den = self.den.sqrt()?;
+        if num.mul(&num) == self.num && den.mul(&den) == self.den {
+            Some(Rational { num, den })
+        } else {
+            None
+        }
+    }
+
+    /// 整数乗。負の指数は逆数にする。0 の負の乗は None
+    pub fn pow(&self, exp: i64) -> Option<Self> {
+        let e = exp.unsigned_abs();
+        let num = self.num.pow(e);
+        let den = self.den.pow(e);
+        if exp < 0 {
+            Rational::new(den, num)
+        } else {
+            Some(Rational { num, den })
+        }
+    }
+
+    /// 分子と分母の大きい方の bit 数
+    pub fn bits(&self) -> u64 {
+        self.num.bits().max(self.den.bits())
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.is_integer() {
+            self.num.to_i64()
+        } else {
+            None
+        }
+    }
+
+    /// `10/3` のような分数の形式
+    pub fn to_fraction_string(&self) -> String {
+        if self.is_integer() {
+            self.num.to_string()
+        } else {
+            format!("{}/{}", self.num, self.den)
+        }
+    }
+
+    /// 小数点以下 digits 桁までの 10 進数表記と、それで割り切れたかどうかを返す
+    pub fn to_decimal_string(&self, digits: usize) -> (String, bool) {
+        let zero = (BigInt::zero(), BigInt::zero());
+        let (int_part, mut rem) = self.num.abs().div_rem(&self.den).unwrap_or(zero);
+        let ten = BigInt::from_u64(10);
+
+        let mut frac = String::new();
+        for _ in 0..digits {
+            if rem.is_zero() {
+                break;
+            }
+            let (d, r) = rem
+                .mul(&ten)
+                .div_rem(&self.den)
+                .unwrap_or_else(|| (BigInt::zero(), BigInt::zero()));
+            frac.push_str(&d.to_string());
+            rem = r;
+        }
+
+        let sign = if self.is_negative() { "-" } else { "" };
+        let text = if frac.is_empty() {
+            format!("{}{}", sign, int_part)
+        } else {
+            format!("{}{}.{}", sign, int_part, frac)
+        };
+        (text, rem.is_zero())
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.num.mul(&other.den).cmp(&other.num.mul(&self.den))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        match s.strip_prefix('-') {
+            Some(digits) => BigInt::parse_decimal(digits).unwrap().neg(),
+            None => BigInt::parse_decimal(s).unwrap(),
+        }
+    }
+
+    fn ratio(num: i64, den: i64) -> Rational {
+        Rational::new(BigInt::from_i64(num), BigInt::from_i64(den)).unwrap()
+    }
+
+    #[test]
+    fn test_bigint_display() {
+        assert_eq!(BigInt::zero().to_string(), "0");
+        assert_eq!(BigInt::from_i64(-42).to_string(), "-42");
+        assert_eq!(
+            BigInt::from_i64(i64::MIN).to_string(),
+            "-9223372036854775808"
+        );
+        assert_eq!(
+            big("123456789012345678901234567890").to_string(),
+            "123456789012345678901234567890"
+        );
+        assert_eq!(big("000123").to_string(), "123");
+        assert_eq!(BigInt::parse_decimal("12a"), None);
+    }
+
+    #[test]
+    fn test_bigint_arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!(a.add(&b).to_string(), "-864197532086419753208641975320");
+        assert_eq!(a.sub(&b).to_string(), "1111111110111111111011111111100");
+        assert_eq!(
+            a.mul(&b).to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+
+        let (q, r) = b.div_rem(&a).unwrap();
+        assert_eq!(q.to_string(), "-8");
+        assert_eq!(r.to_string(), "-9000000000900000000090");
+        assert_eq!(a.div_rem(&BigInt::zero()), None);
+
+        assert_eq!(
+            BigInt::from_u64(2).pow(200).to_string(),
+            "1606938044258990275541962092341162602522202993782792835301376"
+        );
+        assert_eq!(big("462").gcd(&big("-1071")).to_string(), "21");
+    }
+
+    #[test]
+    fn test_bigint_div_rem() {
+        // 商の見積もりの補正と足し戻しが起きやすい、上位が詰まった値も含めて確かめる
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut limbs = |len: usize| {
+            (0..len)
+                .map(|i| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    match (seed % 4, i) {
+                        (0, _) => u32::MAX,
+                        (1, 0) => 0,
+                        _ => seed as u32,
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        for (a_len, b_len) in [(2, 2), (3, 2), (8, 3), (40, 17), (130, 64)] {
+            for _ in 0..20 {
+                let a = BigInt::from_parts(false, limbs(a_len));
+                let b = BigInt::from_parts(false, limbs(b_len));
+                if b.is_zero() {
+                    continue;
+                }
+                let (q, r) = a.div_rem(&b).unwrap();
+                assert_eq!(q.mul(&b).add(&r), a);
+                assert!(!r.is_negative() && r < b);
+            }
+        }
+
+        let a = big("340282366920938463463374607431768211455");
+        let b = big("18446744073709551617");
+        assert_eq!(a.div_rem(&b), Some((big("18446744073709551615"), big("0"))));
+    }
+
+    #[test]
+    fn test_bigint_gcd() {
+        assert_eq!(big("462").gcd(&big("-1071")), big("21"));
+        assert_eq!(big("0").gcd(&big("-5")), big("5"));
+        assert_eq!(big("7").gcd(&big("0")), big("7"));
+        let a = BigInt::from_u64(3).mul(&BigInt::one().shl(100));
+        let b = BigInt::from_u64(9).mul(&BigInt::one().shl(50));
+        assert_eq!(a.gcd(&b), BigInt::from_u64(3).shl(50));
+        let a = BigInt::from_u64(2).pow(300).sub(&BigInt::one());
+        let b = BigInt::from_u64(2).pow(200).sub(&BigInt::one());
+        assert_eq!(a.gcd(&b), BigInt::from_u64(2).pow(100).sub(&BigInt::one()));
+    }
+
+    #[test]
+    fn test_bigint_to_i64() {
+        assert_eq!(BigInt::from_i64(i64::MAX).to_i64(), Some(i64::MAX));
+        assert_eq!(BigInt::from_i64(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(ratio(2, 4), ratio(1, 2));
+        assert_eq!(ratio(1, -2), ratio(-1, 2));
+        assert_eq!(ratio(1, 3).add(&ratio(1, 6)), ratio(1, 2));
+        assert_eq!(ratio(1, 3).sub(&ratio(1, 2)), ratio(-1, 6));
+        assert_eq!(ratio(2, 3).mul(&ratio(3, 4)), ratio(1, 2));
+        assert_eq!(ratio(1, 2).div(&ratio(0, 1)), None);
+        assert_eq!(ratio(7, 2).rem(&ratio(1, 1)), Some(ratio(1, 2)));
+        assert_eq!(ratio(-7, 1).rem(&ratio(3, 1)), Some(ratio(-1, 1)));
+        assert_eq!(ratio(2, 3).pow(-2), Some(ratio(9, 4)));
+        assert_eq!(ratio(0, 1).pow(-1), None);
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert_eq!(Rational::parse_decimal("1.25"), Some(ratio(5, 4)));
+        assert_eq!(Rational::parse_decimal("0.1"), Some(ratio(1, 10)));
+    }
+
+    #[test]
+    fn test_bigint_bitwise() {
+        let n = |x: i64| BigInt::from_i64(x);
+        assert_eq!(n(12).and(&n(10)), n(8));
+        assert_eq!(n(12).or(&n(10)), n(14));
+        assert_eq!(n(12).xor(&n(10)), n(6));
+        assert_eq!(n(-1).and(&n(255)), n(255));
+        assert_eq!(n(-12).and(&n(-10)), n(-12 & -10));
+        assert_eq!(n(-12).or(&n(10)), n(-12 | 10));
+        assert_eq!(n(-12).xor(&n(-10)), n(-12 ^ -10));
+        assert_eq!(n(0).not(), n(-1));
+        assert_eq!(n(5).not(), n(-6));
+        assert_eq!(n(1).shl(100).to_string(), "1267650600228229401496703205376");
+        assert_eq!(n(-3).shl(2), n(-12));
+        assert_eq!(n(1).shl(100).shr(98), n(4));
+        assert_eq!(n(-5).shr(1), n(-3));
+        assert_eq!(n(-1).shr(1000), n(-1));
+        assert_eq!(n(7).shr(1000), n(0));
+    }
+
+    #[test]
+    fn test_bigint_radix() {
+        assert_eq!(BigInt::parse_radix("ff", 16), Some(big("255")));
+        assert_eq!(BigInt::parse_radix("FF", 16), Some(big("255")));
+        assert_eq!(BigInt::parse_radix("777", 8), Some(big("511")));
+        assert_eq!(BigInt::parse_radix("102", 2), None);
+        assert_eq!(big("255").to_string_radix(16), "ff");
+        assert_eq!(big("-10").to_string_radix(2), "-1010");
+        assert_eq!(big("0").to_string_radix(8), "0");
+    }
+
+    #[test]
+    fn test_bigint_sqrt() {
+        assert_eq!(big("0").sqrt(), Some(big("0")));
+        assert_eq!(big("1").sqrt(), Some(big("1")));
+        assert_eq!(big("15").sqrt(), Some(big("3")));
+        assert_eq!(big("16").sqrt(), Some(big("4")));
+        assert_eq!(
+            big("1000000000000000000000000000000000000").sqrt(),
+            Some(big("1000000000000000000"))
+        );
+        assert_eq!(big("-4").sqrt(), None);
+    }
+
+    #[test]
+    fn test_rational_f64() {
+        assert_eq!(ratio(1, 4).to_f64(), 0.25);
+        assert_eq!(ratio(-10, 3).to_f64(), -10.0 / 3.0);
+        assert_eq!(
+            Rational::from_integer(big(
+                "1606938044258990275541962092341162602522202993782792835301376"
+            ))
+            .to_f64(),
+            2f64.powi(200)
+        );
+        assert_eq!(Rational::from_f64(0.375), Some(ratio(3, 8)));
+        assert_eq!(Rational::from_f64(-2.0), Some(ratio(-2, 1)));
+        assert_eq!(
+            Rational::from_f64(1e20).unwrap().to_fraction_string(),
+            "100000000000000000000"
+        );
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_rational_round_sqrt() {
+        assert_eq!(ratio(5, 2).round(), ratio(3, 1));
+        assert_eq!(ratio(-5, 2).round(), ratio(-3, 1));
+        assert_eq!(ratio(7, 3).round(), ratio(2, 1));
+        assert_eq!(ratio(4, 9).exact_sqrt(), Some(ratio(2, 3)));
+        assert_eq!(ratio(2, 1).exact_sqrt(), None);
+    }
+
+    #[test]
+    fn test_rational_display() {
+        assert_eq!(ratio(10, 3).to_fraction_string(), "10/3");
+        assert_eq!(ratio(-4, 2).to_fraction_string(), "-2");
+        assert_eq!(
+            ratio(1, 4).to_decimal_string(10),
+            ("0.25".to_string(), true)
+        );
+        assert_eq!(
+            ratio(-10, 3).to_decimal_string(5),
+            ("-3.33333".to_string(), false)
+        );
+        assert_eq!(ratio(3, 1).to_decimal_string(5), ("3".to_string(), true));
+    }
+}
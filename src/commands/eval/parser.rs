@@ -0,0 +1,796 @@
+use super::number::{BigInt, Rational};
+use super::tokenizer::Token;
+use super::EvalError;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+
+impl Operator {
+    fn from_token(token: &Token) -> Option<Operator> {
+        match token {
+            Token::Plus => Some(Operator::Add),
+            Token::Minus => Some(Operator::Sub),
+            Token::Star => Some(Operator::Mul),
+            Token::Slash => Some(Operator::Div),
+            Token::Percent => Some(Operator::Mod),
+            Token::Hat => Some(Operator::Pow),
+            Token::Ampersand => Some(Operator::BitAnd),
+            Token::Pipe => Some(Operator::BitOr),
+            Token::Ident(name) if name == "xor" => Some(Operator::BitXor),
+            Token::ShiftLeft => Some(Operator::Shl),
+            Token::ShiftRight => Some(Operator::Shr),
+            _ => None,
+        }
+    }
+
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self,
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::Shl | Operator::Shr
+        )
+    }
+
+    // (左の結合力, 右の結合力)。右の方が小さいものは右結合になる
+    // ビット演算は Python と同じく | < xor < & < シフト < 算術演算 の順に弱い
+    fn binding_power(&self) -> (u8, u8) {
+        match self {
+            Operator::BitOr => (1, 2),
+            Operator::BitXor => (3, 4),
+            Operator::BitAnd => (5, 6),
+            Operator::Shl | Operator::Shr => (7, 8),
+            Operator::Add | Operator::Sub => (9, 10),
+            Operator::Mul | Operator::Div | Operator::Mod => (11, 12),
+            Operator::Pow => (15, 14),
+        }
+    }
+}
+
+// 単項演算子の結合力。-2^2 は -(2^2) になるように ^ より弱くする
+const PREFIX_BINDING_POWER: u8 = 13;
+// 3km のような数と名前の間の暗黙の掛け算。-3km は -(3km)、2m^2 は 2(m^2)、100km/h は (100km)/h
+const IMPLICIT_MUL_BINDING_POWER: u8 = 14;
+// 後置の ! は一番強く結合する。2^3! は 2^(3!)
+const POSTFIX_BINDING_POWER: u8 = 17;
+// 単位の変換 `in` は一番弱く結合する。1km + 1m in m は (1km + 1m) in m
+const CONVERT_BINDING_POWER: u8 = 0;
+
+// 演算子として使う名前。変数や単位としては読まない
+fn is_keyword(name: &str) -> bool {
+    name == "xor" || name == "in" || name == "as"
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnaryOperator {
+    Plus,
+    Minus,
+    BitNot,
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Mod => "%",
+            Operator::Pow => "^",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "xor",
+            Operator::Shl => "<<",
+            Operator::Shr => ">>",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl std::fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnaryOperator::Plus => write!(f, "+"),
+            UnaryOperator::Minus => write!(f, "-"),
+            UnaryOperator::BitNot => write!(f, "~"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Number(Rational),
+    Unary(UnaryOperator, Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Factorial(Box<Expr>),
+    // 名前と入力中の位置
+    Variable(String, usize),
+    Call(String, Vec<Expr>, usize),
+    // 1.5GiB in MB。変換先の単位の式と、表示用のその文字列
+    Convert(Box<Expr>, Box<Expr>, String),
+    // 日時のリテラルと入力中の位置
+    DateTime(String, usize),
+    // 1700000000 as date。変換先の名前と入力中の位置
+    Cast(Box<Expr>, String, usize),
+    // [3, 5, 8]。要素と [ の位置
+    List(Vec<Expr>, usize),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Statement {
+    // x = 1 + 2
+    Assign(String, Expr),
+    Expr(Expr),
+}
+
+// 10 進数の小数と、0x, 0o, 0b で始まる整数
+fn parse_number(literal: &str) -> Option<Rational> {
+    let radix = match literal.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => return Rational::parse_decimal(literal),
+    };
+    let digits = literal[2..].replace('_', "");
+    BigInt::parse_radix(&digits, radix).map(Rational::from_integer)
+}
+
+pub fn cast_target_error(column: usize) -> EvalError {
+    EvalError::at("as の後には date か unix を指定してください", column)
+}
+
+fn number_expr(literal: &str, column: usize) -> Result<Expr, EvalError> {
+    match parse_number(literal) {
+        Some(n) => Ok(Expr::Number(n)),
+        None => Err(EvalError::at(
+            &format!("数値として読めません: {}", literal),
+            column,
+        )),
+    }
+}
+
+// AST の深さの上限。評価や途中経過の表示も再帰するので、深すぎる式はスタックを使い切る
+pub(super) const MAX_DEPTH: usize = 100;
+
+// token と入力中の位置 (1 始まりの文字数) の組から AST を組み立てる
+// 演算子の優先順位は Pratt parser で扱う
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    // 入力の終端の位置。式が途中で終わったときのエラー表示に使う
+    end: usize,
+    // 組み立て中の式の根からの深さ
+    depth: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<(Token, usize)>, end: usize) -> Self {
+        Parser {
+            tokens,
+            pos: 0,
+            end,
+            depth: 0,
+        }
+    }
+
+    // 式を 1 段深くする。1 + 1 + ... のように左に伸びる式も 1 項ごとに深くなる
+    fn nest(&mut self) -> Result<(), EvalError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            let column = self.peek().map_or(self.end, |(_, column)| *column);
+            return Err(EvalError::at("式が長すぎるか、入れ子が深すぎます", column));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&(Token, usize)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse(&mut self) -> Result<Expr, EvalError> {
+        let expr = self.parse_expr(0)?;
+        match self.peek() {
+            None => Ok(expr),
+            Some((Token::RightParen, column)) => {
+                Err(EvalError::at("対応する ( がありません", *column))
+            }
+            Some((token, column)) => Err(EvalError::at(
+                &format!("予期しないトークンです: {}", token),
+                *column,
+            )),
+        }
+    }
+
+    fn parse_expr(&mut self, min_bp: u8) -> Result<Expr, EvalError> {
+        let depth = self.depth;
+        let expr = self.parse_operators(min_bp);
+        self.depth = depth;
+        expr
+    }
+
+    fn parse_operators(&mut self, min_bp: u8) -> Result<Expr, EvalError> {
+        self.nest()?;
+        let mut lhs = self.parse_prefix()?;
+
+        while let Some((token, _)) = self.peek() {
+            if *token == Token::Bang {
+                if POSTFIX_BINDING_POWER < min_bp {
+                    break;
+                }
+                self.next();
+                self.nest()?;
+                lhs = Expr::Factorial(Box::new(lhs));
+                continue;
+            }
+
+            if let Token::Ident(name) = token {
+                if name == "as" {
+                    if CONVERT_BINDING_POWER < min_bp {
+                        break;
+                    }
+                    self.next();
+                    self.nest()?;
+                    lhs = match self.next() {
+                        Some((Token::Ident(target), column)) => {
+                            Expr::Cast(Box::new(lhs), target, column)
+                        }
+                        Some((_, column)) => return Err(cast_target_error(column)),
+                        None => return Err(cast_target_error(self.end)),
+                    };
+                    continue;
+                }
+                if name == "in" {
+                    if CONVERT_BINDING_POWER < min_bp {
+                        break;
+                    }
+                    self.next();
+                    self.nest()?;
+                    let start = self.pos;
+                    let unit = self.parse_expr(CONVERT_BINDING_POWER + 1)?;
+                    let text = self.tokens[start..self.pos.min(self.tokens.len())]
+                        .iter()
+                        .map(|(token, _)| token.to_string())
+                        .collect::<String>();
+                    lhs = Expr::Convert(Box::new(lhs), Box::new(unit), text);
+                    continue;
+                }
+            }
+
+            let op = match Operator::from_token(token) {
+                Some(op) => op,
+                None => break,
+            };
+            let (left_bp, right_bp) = op.binding_power();
+            if left_bp < min_bp {
+                break;
+            }
+            self.next();
+            self.nest()?;
+            let rhs = self.parse_expr(right_bp)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    // 数の直後に名前が続いていれば掛け算とみなす
+    fn juxtapose(&mut self, number: Expr) -> Result<Expr, EvalError> {
+        match self.peek() {
+            Some((Token::Ident(name), _)) if !is_keyword(name) => {
+                let rhs = self.parse_expr(IMPLICIT_MUL_BINDING_POWER)?;
+                Ok(Expr::Binary(Operator::Mul, Box::new(number), Box::new(rhs)))
+            }
+            _ => Ok(number),
+        }
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expr, EvalError> {
+        match self.next() {
+            Some((Token::Number(n), column)) => {
+                let number = number_expr(&n, column)?;
+                self.juxtapose(number)
+            }
+            Some((Token::Minus, _)) => {
+                // -40F の符号は単位ではなく数に付ける。0 の位置がずれている単位で意味が変わる
+                if let (Some((Token::Number(n), column)), Some((Token::Ident(name), _))) =
+                    (self.peek().cloned(), self.tokens.get(self.pos + 1))
+                {
+                    if !is_keyword(name) {
+                        self.next();
+                        let number = number_expr(&n, column)?;
+                        return self.juxtapose(Expr::Unary(UnaryOperator::Minus, Box::new(number)));
+                    }
+                }
+                let operand = self.parse_expr(PREFIX_BINDING_POWER)?;
+                Ok(Expr::Unary(UnaryOperator::Minus, Box::new(operand)))
+            }
+            Some((Token::Plus, _)) => {
+                let operand = self.parse_expr(PREFIX_BINDING_POWER)?;
+                Ok(Expr::Unary(UnaryOperator::Plus, Box::new(operand)))
+            }
+            Some((Token::Tilde, _)) => {
+                let operand = self.parse_expr(PREFIX_BINDING_POWER)?;
+                Ok(Expr::Unary(UnaryOperator::BitNot, Box::new(operand)))
+            }
+            Some((Token::DateTime(literal), column)) => Ok(Expr::DateTime(literal, column)),
+            Some((Token::Ident(name), column)) => {
+                if let Some((Token::LeftParen, _)) = self.peek() {
+                    let (_, paren) = self.next().unwrap();
+                    let args = self.parse_args(paren)?;
+                    Ok(Expr::Call(name, args, column))
+                } else {
+                    Ok(Expr::Variable(name, column))
+                }
+            }
+            Some((Token::LeftBracket, column)) => {
+                let elements = self.parse_list(Token::RightBracket, "]", column)?;
+                Ok(Expr::List(elements, column))
+            }
+            Some((Token::LeftParen, column)) => {
+                let expr = self.parse_expr(0)?;
+                match self.next() {
+                    Some((Token::RightParen, _)) => Ok(expr),
+                    Some((_, _)) | None => Err(EvalError::at("対応する ) がありません", column)),
+                }
+            }
+            Some((token, column)) => Err(EvalError::at(
+                &format!("式が必要な位置に {} があります", token),
+                column,
+            )),
+            None => Err(EvalError::at("式が途中で終わっています", self.end)),
+        }
+    }
+
+    // 関数呼び出しの引数を , 区切りで読む。( は読み終わっている
+    fn parse_args(&mut self, paren: usize) -> Result<Vec<Expr>, EvalError> {
+        self.parse_list(Token::RightParen, ")", paren)
+    }
+
+    // close までの式を , 区切りで読む。開き括弧は読み終わっている
+    fn parse_list(
+        &mut self,
+        close: Token,
+        symbol: &str,
+        open: usize,
+    ) -> Result<Vec<Expr>, EvalError> {
+        let mut exprs = Vec::new();
+        if self.peek().map(|(token, _)| token) == Some(&close) {
+            self.next();
+            return Ok(exprs);
+        }
+        loop {
+            exprs.push(self.parse_expr(0)?);
+            match self.next() {
+                Some((Token::Comma, _)) => continue,
+                Some((token, _)) if token == close => return Ok(exprs),
+                Some((_, _)) | None => {
+                    return Err(EvalError::at(
+                        &format!("対応する {} がありません", symbol),
+                        open,
+                    ))
+                }
+            }
+        }
+    }
+}
+
+pub fn parse(tokens: Vec<(Token, usize)>, end: usize) -> Result<Expr, EvalError> {
+    Parser::new(tokens, end).parse()
+}
+
+// 先頭が `名前 =` なら代入として読む
+pub fn parse_statement(tokens: Vec<(Token, usize)>, end: usize) -> Result<Statement, EvalError> {
+    if let [(Token::Ident(name), _), (Token::Equal, _), ..] = tokens.as_slice() {
+        let name = name.clone();
+        let expr = parse(tokens[2..].to_vec(), end)?;
+        return Ok(Statement::Assign(name, expr));
+    }
+    Ok(Statement::Expr(parse(tokens, end)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::evaluator::{eval, Context, Environment, Mode, Value};
+    use super::*;
+
+    fn tokens(tokens: Vec<Token>) -> Vec<(Token, usize)> {
+        tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| (t, i + 1))
+            .collect()
+    }
+
+    fn num(n: &str) -> Token {
+        Token::Number(n.to_string())
+    }
+
+    fn int(n: i64) -> Box<Expr> {
+        Box::new(Expr::Number(Rational::from_i64(n)))
+    }
+
+    fn eval_int(expr: Expr) -> Result<i64, EvalError> {
+        let ctx = Context {
+            mode: Mode::Rational,
+            now: chrono::Utc::now(),
+            env: &Environment::new(),
+        };
+        match eval(&expr, &ctx)?.value {
+            Value::Exact(n) => Ok(n.to_i64().unwrap()),
+            Value::Approx(x) => Ok(x as i64),
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        let tokens1 = tokens(vec![num("2"), Token::Plus, num("2")]);
+        let result1 = parse(tokens1, 4).and_then(eval_int);
+        assert_eq!(result1, Ok(4));
+
+        let tokens2 = tokens(vec![num("2"), Token::Plus, num("2"), Token::Star, num("2")]);
+        let result2 = parse(tokens2, 6);
+        assert_eq!(
+            result2,
+            Ok(Expr::Binary(
+                Operator::Add,
+                int(2),
+                Box::new(Expr::Binary(Operator::Mul, int(2), int(2)))
+            ))
+        );
+
+        let tokens3 = tokens(vec![
+            Token::LeftParen,
+            num("1"),
+            Token::Plus,
+            num("1"),
+            Token::RightParen,
+            Token::Star,
+            num("10"),
+            Token::Slash,
+            num("5"),
+        ]);
+        let result3 = parse(tokens3, 10).and_then(eval_int);
+        assert_eq!(result3, Ok(4));
+
+        let tokens4 = tokens(vec![
+            Token::LeftParen,
+            num("10000"),
+            Token::Plus,
+            num("1"),
+            Token::RightParen,
+            Token::Star,
+            Token::LeftParen,
+            num("10"),
+            Token::Slash,
+            num("5"),
+            Token::RightParen,
+        ]);
+        let result4 = parse(tokens4, 12).and_then(eval_int);
+        assert_eq!(result4, Ok(20002));
+    }
+
+    #[test]
+    fn test_associativity() {
+        // 2^3^2 = 2^(3^2)
+        let result = parse(
+            tokens(vec![num("2"), Token::Hat, num("3"), Token::Hat, num("2")]),
+            6,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Pow,
+                int(2),
+                Box::new(Expr::Binary(Operator::Pow, int(3), int(2)))
+            ))
+        );
+
+        // 8-3-2 = (8-3)-2
+        let result = parse(
+            tokens(vec![
+                num("8"),
+                Token::Minus,
+                num("3"),
+                Token::Minus,
+                num("2"),
+            ]),
+            6,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Sub,
+                Box::new(Expr::Binary(Operator::Sub, int(8), int(3))),
+                int(2)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unary() {
+        // -2^2 = -(2^2)
+        let result = parse(
+            tokens(vec![Token::Minus, num("2"), Token::Hat, num("2")]),
+            5,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Unary(
+                UnaryOperator::Minus,
+                Box::new(Expr::Binary(Operator::Pow, int(2), int(2)))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bitwise_precedence() {
+        // 1 | 2 xor 3 & 4 << 5 + 6 = 1 | (2 xor (3 & (4 << (5 + 6))))
+        let result = parse(
+            tokens(vec![
+                num("1"),
+                Token::Pipe,
+                num("2"),
+                Token::Ident("xor".to_string()),
+                num("3"),
+                Token::Ampersand,
+                num("4"),
+                Token::ShiftLeft,
+                num("5"),
+                Token::Plus,
+                num("6"),
+            ]),
+            12,
+        );
+        let shift = Expr::Binary(
+            Operator::Shl,
+            int(4),
+            Box::new(Expr::Binary(Operator::Add, int(5), int(6))),
+        );
+        let and = Expr::Binary(Operator::BitAnd, int(3), Box::new(shift));
+        let xor = Expr::Binary(Operator::BitXor, int(2), Box::new(and));
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(Operator::BitOr, int(1), Box::new(xor)))
+        );
+
+        // ~1 & 3 = (~1) & 3
+        let result = parse(
+            tokens(vec![Token::Tilde, num("1"), Token::Ampersand, num("3")]),
+            5,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::BitAnd,
+                Box::new(Expr::Unary(UnaryOperator::BitNot, int(1))),
+                int(3)
+            ))
+        );
+    }
+
+    fn ident(name: &str) -> Token {
+        Token::Ident(name.to_string())
+    }
+
+    fn var(name: &str, column: usize) -> Box<Expr> {
+        Box::new(Expr::Variable(name.to_string(), column))
+    }
+
+    #[test]
+    fn test_implicit_mul() {
+        // 100km/h = (100 * km) / h
+        let result = parse(
+            tokens(vec![num("100"), ident("km"), Token::Slash, ident("h")]),
+            5,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Div,
+                Box::new(Expr::Binary(Operator::Mul, int(100), var("km", 2))),
+                var("h", 4)
+            ))
+        );
+
+        // 2m^2 = 2 * (m^2)
+        let result = parse(tokens(vec![num("2"), ident("m"), Token::Hat, num("2")]), 5);
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Mul,
+                int(2),
+                Box::new(Expr::Binary(Operator::Pow, var("m", 2), int(2)))
+            ))
+        );
+
+        // -40F = (-40) * F
+        let result = parse(tokens(vec![Token::Minus, num("40"), ident("F")]), 4);
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Mul,
+                Box::new(Expr::Unary(UnaryOperator::Minus, int(40))),
+                var("F", 3)
+            ))
+        );
+
+        // 1 xor 2 は掛け算ではない
+        let result = parse(tokens(vec![num("1"), ident("xor"), num("2")]), 4);
+        assert_eq!(result, Ok(Expr::Binary(Operator::BitXor, int(1), int(2))));
+    }
+
+    #[test]
+    fn test_convert() {
+        // 1km + 1m in m/s = (1km + 1m) in (m/s)
+        let result = parse(
+            tokens(vec![
+                num("1"),
+                ident("km"),
+                Token::Plus,
+                num("1"),
+                ident("m"),
+                ident("in"),
+                ident("m"),
+                Token::Slash,
+                ident("s"),
+            ]),
+            10,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Convert(
+                Box::new(Expr::Binary(
+                    Operator::Add,
+                    Box::new(Expr::Binary(Operator::Mul, int(1), var("km", 2))),
+                    Box::new(Expr::Binary(Operator::Mul, int(1), var("m", 5)))
+                )),
+                Box::new(Expr::Binary(Operator::Div, var("m", 7), var("s", 9))),
+                "m/s".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        // 2026-10-18 + 1d as unix = (2026-10-18 + 1d) as unix
+        let result = parse(
+            tokens(vec![
+                Token::DateTime("2026-10-18".to_string()),
+                Token::Plus,
+                num("1"),
+                ident("d"),
+                ident("as"),
+                ident("unix"),
+            ]),
+            7,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::Cast(
+                Box::new(Expr::Binary(
+                    Operator::Add,
+                    Box::new(Expr::DateTime("2026-10-18".to_string(), 1)),
+                    Box::new(Expr::Binary(Operator::Mul, int(1), var("d", 4)))
+                )),
+                "unix".to_string(),
+                6
+            ))
+        );
+
+        let result = parse(tokens(vec![num("1"), ident("as")]), 3);
+        assert_eq!(result, Err(cast_target_error(3)));
+    }
+
+    #[test]
+    fn test_list() {
+        // [1, 2 * 3, []]
+        let result = parse(
+            tokens(vec![
+                Token::LeftBracket,
+                num("1"),
+                Token::Comma,
+                num("2"),
+                Token::Star,
+                num("3"),
+                Token::Comma,
+                Token::LeftBracket,
+                Token::RightBracket,
+                Token::RightBracket,
+            ]),
+            11,
+        );
+        assert_eq!(
+            result,
+            Ok(Expr::List(
+                vec![
+                    Expr::Number(Rational::from_i64(1)),
+                    Expr::Binary(Operator::Mul, int(2), int(3)),
+                    Expr::List(Vec::new(), 8),
+                ],
+                1
+            ))
+        );
+
+        let result = parse(
+            tokens(vec![Token::LeftBracket, num("1"), Token::RightParen]),
+            4,
+        );
+        assert_eq!(result, Err(EvalError::at("対応する ] がありません", 1)));
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("0xff"), Some(Rational::from_i64(255)));
+        assert_eq!(parse_number("0b1010_1010"), Some(Rational::from_i64(170)));
+        assert_eq!(parse_number("0o17"), Some(Rational::from_i64(15)));
+        assert_eq!(parse_number("0x"), None);
+        assert_eq!(parse_number("0b12"), None);
+    }
+
+    #[test]
+    fn test_factorial() {
+        // -3! = -(3!), 2^3! = 2^(3!)
+        let result = parse(tokens(vec![Token::Minus, num("3"), Token::Bang]), 4);
+        assert_eq!(
+            result,
+            Ok(Expr::Unary(
+                UnaryOperator::Minus,
+                Box::new(Expr::Factorial(int(3)))
+            ))
+        );
+
+        let result = parse(tokens(vec![num("2"), Token::Hat, num("3"), Token::Bang]), 5);
+        assert_eq!(
+            result,
+            Ok(Expr::Binary(
+                Operator::Pow,
+                int(2),
+                Box::new(Expr::Factorial(int(3)))
+            ))
+        );
+    }
+
+    fn parse_str(input: &str) -> Result<Expr, EvalError> {
+        let tokens = super::super::tokenizer::tokenize(input)?;
+        parse(tokens, input.chars().count() + 1)
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let error = Err(EvalError::at("式が長すぎるか、入れ子が深すぎます", 101));
+        assert_eq!(parse_str(&format!("{}1", "-".repeat(500))), error);
+        assert_eq!(
+            parse_str(&format!("{}1{}", "(".repeat(2500), ")".repeat(2500))),
+            error
+        );
+        assert!(parse_str(&format!("{}1{}", "abs(".repeat(500), ")".repeat(500))).is_err());
+        assert!(parse_str(&vec!["1"; 2500].join("+")).is_err());
+        assert!(parse_str(&format!("1{}", "!".repeat(2500))).is_err());
+
+        // 普通の長さの式は読める
+        assert_eq!(
+            parse_str(&vec!["1"; 50].join("+")).and_then(eval_int),
+            Ok(50)
+        );
+        assert_eq!(
+            parse_str(&format!("{}1{}", "(".repeat(50), ")".repeat(50))).and_then(eval_int),
+            Ok(1)
+        );
+    }
+}
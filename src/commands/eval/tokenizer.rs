@@ -0,0 +1,422 @@
+use super::EvalError;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Hat,
+    Bang,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Equal,
+    Ampersand,
+    Pipe,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+
+    // 数値リテラルは桁数の制限をかけないよう文字列のまま持つ
+    Number(String),
+    // 関数名、定数、変数
+    Ident(String),
+    // 2026-10-18 や 2026-10-18 09:30
+    DateTime(String),
+}
+
+fn char_to_token(c: char) -> Option<Token> {
+    match c {
+        '+' => Some(Token::Plus),
+        '-' => Some(Token::Minus),
+        '*' => Some(Token::Star),
+        '/' => Some(Token::Slash),
+        '%' => Some(Token::Percent),
+        '^' => Some(Token::Hat),
+        '!' => Some(Token::Bang),
+        '(' => Some(Token::LeftParen),
+        ')' => Some(Token::RightParen),
+        '[' => Some(Token::LeftBracket),
+        ']' => Some(Token::RightBracket),
+        ',' => Some(Token::Comma),
+        '=' => Some(Token::Equal),
+        '&' => Some(Token::Ampersand),
+        '|' => Some(Token::Pipe),
+        '~' => Some(Token::Tilde),
+        _ => None,
+    }
+}
+
+fn is_digit(c: char) -> bool {
+    c.is_digit(10)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// start から YYYY-MM-DD が始まっていればその長さを返す。
+// 後ろに T か空白区切りで HH:MM か HH:MM:SS が続いていればそれも含める
+fn date_literal_len(chars: &[char], start: usize) -> usize {
+    let matches = |pattern: &str, from: usize| {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(i, p)| match chars.get(from + i) {
+                Some(&c) if p == '0' => is_digit(c),
+                Some(&c) => c == p,
+                None => false,
+            })
+    };
+    if !matches("0000-00-00", start) {
+        return 0;
+    }
+    let time = start + 10;
+    let has_separator = matches!(chars.get(time), Some('T') | Some(' '));
+    if !has_separator || !matches("00:00", time + 1) {
+        return 10;
+    }
+    if matches(":00", time + 6) {
+        19
+    } else {
+        16
+    }
+}
+
+// 入力を token と位置 (1 始まりの文字数) の組に分解する
+pub fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, EvalError> {
+    let mut tokens = Vec::new();
+    let all_chars = input.chars().collect::<Vec<_>>();
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let column = i + 1;
+
+        // 空白は無視
+        if c.is_whitespace() {
+            continue;
+        }
+
+        // 日付は引き算より優先して読む
+        let len = date_literal_len(&all_chars, i);
+        if len > 0 {
+            let literal = all_chars[i..i + len].iter().collect::<String>();
+            for _ in 1..len {
+                chars.next();
+            }
+            tokens.push((Token::DateTime(literal), column));
+            continue;
+        }
+
+        // 0x, 0o, 0b で始まるものは 16, 8, 2 進数。桁の区切りに _ を使える
+        if c == '0' && matches!(chars.peek(), Some(&(_, 'x' | 'o' | 'b'))) {
+            let mut num = String::from("0");
+            while let Some(&(_, c)) = chars.peek() {
+                if !is_ident_continue(c) {
+                    break;
+                }
+                num.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Number(num), column));
+            continue;
+        }
+
+        // 数字の場合数字以外がくるまで繰り返す。小数点は 1 つまで
+        if is_digit(c) || (c == '.' && matches!(chars.peek(), Some(&(_, c)) if is_digit(c))) {
+            let mut num = String::new();
+            num.push(c);
+            let mut has_point = c == '.';
+
+            while let Some(&(j, c)) = chars.peek() {
+                if is_digit(c) {
+                    num.push(c);
+                    chars.next();
+                } else if c == '.' && !has_point {
+                    has_point = true;
+                    num.push(c);
+                    chars.next();
+                } else if c == '.' {
+                    return Err(EvalError::at("予期しない文字です: .", j + 1));
+                } else {
+                    break;
+                }
+            }
+
+            if num.ends_with('.') {
+                return Err(EvalError::at("小数点の後に数字がありません", column));
+            }
+            tokens.push((Token::Number(num), column));
+            continue;
+        }
+
+        // 識別子は英字か _ で始まり、英数字と _ が続く
+        if is_ident_start(c) {
+            let mut ident = String::new();
+            ident.push(c);
+            while let Some(&(_, c)) = chars.peek() {
+                if !is_ident_continue(c) {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Ident(ident), column));
+            continue;
+        }
+
+        // << と >> は 2 文字で 1 つ
+        if c == '<' || c == '>' {
+            match chars.peek() {
+                Some(&(_, next)) if next == c => {
+                    chars.next();
+                    let token = if c == '<' {
+                        Token::ShiftLeft
+                    } else {
+                        Token::ShiftRight
+                    };
+                    tokens.push((token, column));
+                    continue;
+                }
+                _ => return Err(EvalError::at(&format!("予期しない文字です: {}", c), column)),
+            }
+        }
+
+        // それ以外の文字はトークンに変換
+        if let Some(token) = char_to_token(c) {
+            tokens.push((token, column));
+            continue;
+        }
+
+        // 数字以外で token に変換できない文字が来たらエラー
+        return Err(EvalError::at(&format!("予期しない文字です: {}", c), column));
+    }
+
+    Ok(tokens)
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Hat => write!(f, "^"),
+            Token::Bang => write!(f, "!"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBracket => write!(f, "["),
+            Token::RightBracket => write!(f, "]"),
+            Token::Comma => write!(f, ","),
+            Token::Equal => write!(f, "="),
+            Token::Ampersand => write!(f, "&"),
+            Token::Pipe => write!(f, "|"),
+            Token::Tilde => write!(f, "~"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::DateTime(literal) => write!(f, "{}", literal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(n: &str) -> Token {
+        Token::Number(n.to_string())
+    }
+
+    fn tokenizer(input: String) -> Result<Vec<Token>, EvalError> {
+        Ok(tokenize(&input)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect())
+    }
+
+    #[test]
+    fn test_char_to_token() {
+        assert_eq!(char_to_token('+'), Some(Token::Plus));
+        assert_eq!(char_to_token('-'), Some(Token::Minus));
+        assert_eq!(char_to_token('*'), Some(Token::Star));
+        assert_eq!(char_to_token('/'), Some(Token::Slash));
+        assert_eq!(char_to_token('%'), Some(Token::Percent));
+        assert_eq!(char_to_token('^'), Some(Token::Hat));
+        assert_eq!(char_to_token('!'), Some(Token::Bang));
+        assert_eq!(char_to_token('('), Some(Token::LeftParen));
+        assert_eq!(char_to_token(')'), Some(Token::RightParen));
+        assert_eq!(char_to_token('['), Some(Token::LeftBracket));
+        assert_eq!(char_to_token(']'), Some(Token::RightBracket));
+        assert_eq!(char_to_token(','), Some(Token::Comma));
+        assert_eq!(char_to_token('='), Some(Token::Equal));
+        assert_eq!(char_to_token('&'), Some(Token::Ampersand));
+        assert_eq!(char_to_token('|'), Some(Token::Pipe));
+        assert_eq!(char_to_token('~'), Some(Token::Tilde));
+        assert_eq!(char_to_token('a'), None);
+    }
+
+    #[test]
+    fn test_is_digit() {
+        assert_eq!(is_digit('0'), true);
+        assert_eq!(is_digit('1'), true);
+        assert_eq!(is_digit('2'), true);
+        assert_eq!(is_digit('3'), true);
+        assert_eq!(is_digit('4'), true);
+        assert_eq!(is_digit('5'), true);
+        assert_eq!(is_digit('6'), true);
+        assert_eq!(is_digit('7'), true);
+        assert_eq!(is_digit('8'), true);
+        assert_eq!(is_digit('9'), true);
+        assert_eq!(is_digit('a'), false);
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        let tokens1 = tokenizer(String::from("2 + 2"));
+        assert_eq!(tokens1, Ok(vec![num("2"), Token::Plus, num("2"),]));
+
+        let tokens2 = tokenizer(String::from("2 + 2 * 2"));
+        assert_eq!(
+            tokens2,
+            Ok(vec![num("2"), Token::Plus, num("2"), Token::Star, num("2"),])
+        );
+
+        let tokens3 = tokenizer(String::from("(1 + 1) * 10 / 5"));
+        assert_eq!(
+            tokens3,
+            Ok(vec![
+                Token::LeftParen,
+                num("1"),
+                Token::Plus,
+                num("1"),
+                Token::RightParen,
+                Token::Star,
+                num("10"),
+                Token::Slash,
+                num("5"),
+            ])
+        );
+
+        let tokens4 = tokenizer(String::from("1000"));
+        assert_eq!(tokens4, Ok(vec![num("1000")]));
+
+        let tokens5 = tokenizer(String::from("1.5 * .25 + 99999999999999999999"));
+        assert_eq!(
+            tokens5,
+            Ok(vec![
+                num("1.5"),
+                Token::Star,
+                num(".25"),
+                Token::Plus,
+                num("99999999999999999999"),
+            ])
+        );
+
+        let tokens6 = tokenizer(String::from("30!"));
+        assert_eq!(tokens6, Ok(vec![num("30"), Token::Bang]));
+
+        let tokens7 = tokenizer(String::from("x_1 = max(2, pi)"));
+        assert_eq!(
+            tokens7,
+            Ok(vec![
+                Token::Ident("x_1".to_string()),
+                Token::Equal,
+                Token::Ident("max".to_string()),
+                Token::LeftParen,
+                num("2"),
+                Token::Comma,
+                Token::Ident("pi".to_string()),
+                Token::RightParen,
+            ])
+        );
+
+        let tokens8 = tokenizer(String::from("~0xFF_FF << 0b10 | 0o7 >> 1 xor 2"));
+        assert_eq!(
+            tokens8,
+            Ok(vec![
+                Token::Tilde,
+                num("0xFF_FF"),
+                Token::ShiftLeft,
+                num("0b10"),
+                Token::Pipe,
+                num("0o7"),
+                Token::ShiftRight,
+                num("1"),
+                Token::Ident("xor".to_string()),
+                num("2"),
+            ])
+        );
+
+        let tokens9 = tokenizer(String::from("2026-12-24 - 2026-10-18T09:30 + 2026-1-1"));
+        assert_eq!(
+            tokens9,
+            Ok(vec![
+                Token::DateTime("2026-12-24".to_string()),
+                Token::Minus,
+                Token::DateTime("2026-10-18T09:30".to_string()),
+                Token::Plus,
+                num("2026"),
+                Token::Minus,
+                num("1"),
+                Token::Minus,
+                num("1"),
+            ])
+        );
+
+        let tokens10 = tokenizer(String::from("2026-10-18 09:30:15 in UTC"));
+        assert_eq!(
+            tokens10,
+            Ok(vec![
+                Token::DateTime("2026-10-18 09:30:15".to_string()),
+                Token::Ident("in".to_string()),
+                Token::Ident("UTC".to_string()),
+            ])
+        );
+
+        let tokens11 = tokenizer(String::from("mean([120ms, 95.5ms])"));
+        assert_eq!(
+            tokens11,
+            Ok(vec![
+                Token::Ident("mean".to_string()),
+                Token::LeftParen,
+                Token::LeftBracket,
+                num("120"),
+                Token::Ident("ms".to_string()),
+                Token::Comma,
+                num("95.5"),
+                Token::Ident("ms".to_string()),
+                Token::RightBracket,
+                Token::RightParen,
+            ])
+        );
+
+        assert_eq!(
+            tokenize("1 < 2"),
+            Err(EvalError::at("予期しない文字です: <", 3))
+        );
+        assert_eq!(
+            tokenize("1 + 2."),
+            Err(EvalError::at("小数点の後に数字がありません", 5))
+        );
+        assert_eq!(
+            tokenize("1.2.3"),
+            Err(EvalError::at("予期しない文字です: .", 4))
+        );
+        assert_eq!(
+            tokenize("1 + $"),
+            Err(EvalError::at("予期しない文字です: $", 5))
+        );
+    }
+}
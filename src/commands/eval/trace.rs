@@ -0,0 +1,298 @@
+use super::evaluator::{eval, Context, Quantity, Value};
+use super::parser::{Expr, UnaryOperator};
+use super::{format_float, DECIMAL_DIGITS};
+use crate::utils::datetime;
+
+// 式をどう読んでどう計算したかの表示方法
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trace {
+    // 構文木に各ノードの値を添える
+    Tree,
+    // 内側から 1 つずつ計算した手順に番号を振る
+    Steps,
+}
+
+impl Trace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(Trace::Tree),
+            "steps" => Some(Trace::Steps),
+            _ => None,
+        }
+    }
+}
+
+// 数や名前のように、それ以上計算の手順に分けないもの
+fn is_leaf(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) | Expr::Variable(..) | Expr::DateTime(..) => true,
+        Expr::Unary(UnaryOperator::Minus, operand) => matches!(**operand, Expr::Number(_)),
+        _ => false,
+    }
+}
+
+// 葉は入力のまま表示する
+fn leaf_text(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => match n.to_decimal_string(DECIMAL_DIGITS) {
+            (decimal, true) => decimal,
+            _ => n.to_fraction_string(),
+        },
+        Expr::Variable(name, _) => name.clone(),
+        Expr::DateTime(literal, _) => literal.clone(),
+        Expr::Unary(op, operand) => format!("{}{}", op, leaf_text(operand)),
+        _ => String::new(),
+    }
+}
+
+// 途中の値は 1 行に収まるよう、分数と単位だけの短い形で表示する
+fn short_quantity(quantity: &Quantity, ctx: &Context) -> String {
+    if let Some(time_zone) = &quantity.instant {
+        let text = quantity
+            .value
+            .to_rational()
+            .and_then(|n| n.trunc().to_i64())
+            .and_then(|timestamp| datetime::format(timestamp, time_zone));
+        return text.unwrap_or_else(|| "(範囲外の日時)".to_string());
+    }
+    if let Some(items) = &quantity.items {
+        let element = Quantity {
+            items: None,
+            ..quantity.clone()
+        };
+        let items = items
+            .iter()
+            .map(|item| {
+                let element = Quantity {
+                    value: item.clone(),
+                    ..element.clone()
+                };
+                short_quantity(&element, ctx)
+            })
+            .collect::<Vec<_>>();
+        return format!("[{}]", items.join(", "));
+    }
+    let value = match quantity.value_in_unit(ctx.mode) {
+        Ok(value) => value,
+        Err(err) => return format!("エラー: {}", err.message),
+    };
+    let amount = match &value {
+        Value::Exact(n) => match n.to_decimal_string(DECIMAL_DIGITS) {
+            (decimal, true) => decimal,
+            _ => n.to_fraction_string(),
+        },
+        Value::Approx(x) => format_float(*x),
+    };
+    match &quantity.unit {
+        Some(unit) => format!("{} {}", amount, unit.name),
+        None if quantity.dimension.is_none() => amount,
+        None => format!("{} {}", amount, quantity.dimension),
+    }
+}
+
+fn value_text(expr: &Expr, ctx: &Context) -> String {
+    match eval(expr, ctx) {
+        Ok(quantity) => short_quantity(&quantity, ctx),
+        Err(err) => format!("エラー: {}", err.message),
+    }
+}
+
+// 演算子の項として並べるときに、区切りが紛れる値は括弧で囲む
+fn operand_text(expr: &Expr, ctx: &Context) -> String {
+    let text = if is_leaf(expr) {
+        leaf_text(expr)
+    } else {
+        value_text(expr, ctx)
+    };
+    if text.contains(' ') || text.contains('/') || text.starts_with('-') {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+// , で区切って並べるときは括弧で囲まない
+fn list_text(exprs: &[Expr], ctx: &Context) -> String {
+    exprs
+        .iter()
+        .map(|expr| match is_leaf(expr) {
+            true => leaf_text(expr),
+            false => value_text(expr, ctx),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// 子を計算済みの値に置き換えた式
+fn reduced_text(expr: &Expr, ctx: &Context) -> String {
+    match expr {
+        // 単項演算子は項が計算済みであることが分かるよう常に括弧で囲む
+        Expr::Unary(op, operand) => match operand_text(operand, ctx) {
+            text if text.starts_with('(') => format!("{}{}", op, text),
+            text => format!("{}({})", op, text),
+        },
+        Expr::Binary(op, lhs, rhs) => format!(
+            "{} {} {}",
+            operand_text(lhs, ctx),
+            op,
+            operand_text(rhs, ctx)
+        ),
+        Expr::Factorial(operand) => format!("{}!", operand_text(operand, ctx)),
+        Expr::Call(name, args, _) => format!("{}({})", name, list_text(args, ctx)),
+        Expr::List(elements, _) => format!("[{}]", list_text(elements, ctx)),
+        Expr::Convert(lhs, _, target) => format!("{} in {}", operand_text(lhs, ctx), target),
+        Expr::Cast(operand, target, _) => format!("{} as {}", operand_text(operand, ctx), target),
+        _ => leaf_text(expr),
+    }
+}
+
+// 計算する順に並べた子。変換先の単位は計算の手順に含めない
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Unary(_, operand) | Expr::Factorial(operand) | Expr::Cast(operand, ..) => {
+            vec![operand]
+        }
+        Expr::Binary(_, lhs, rhs) => vec![lhs, rhs],
+        Expr::Call(_, args, _) | Expr::List(args, _) => args.iter().collect(),
+        Expr::Convert(lhs, ..) => vec![lhs],
+        _ => Vec::new(),
+    }
+}
+
+fn label(expr: &Expr) -> String {
+    match expr {
+        Expr::Unary(op, _) => format!("{}", op),
+        Expr::Binary(op, ..) => format!("{}", op),
+        Expr::Factorial(_) => "!".to_string(),
+        Expr::Call(name, ..) => format!("{}()", name),
+        Expr::List(..) => "[]".to_string(),
+        Expr::Convert(_, _, target) => format!("in {}", target),
+        Expr::Cast(_, target, _) => format!("as {}", target),
+        _ => leaf_text(expr),
+    }
+}
+
+fn push_tree(expr: &Expr, ctx: &Context, prefix: &str, connector: &str, lines: &mut Vec<String>) {
+    // 数はそのままなので値を添えない
+    let line = match expr {
+        Expr::Number(_) | Expr::Unary(..) if is_leaf(expr) => leaf_text(expr),
+        _ => format!("{} → {}", label(expr), value_text(expr, ctx)),
+    };
+    lines.push(format!("{}{}{}", prefix, connector, line));
+
+    let children = if is_leaf(expr) {
+        Vec::new()
+    } else {
+        children(expr)
+    };
+    let child_prefix = match connector {
+        "├─ " => format!("{}│  ", prefix),
+        "└─ " => format!("{}   ", prefix),
+        _ => prefix.to_string(),
+    };
+    for (i, child) in children.iter().enumerate() {
+        let connector = if i + 1 == children.len() {
+            "└─ "
+        } else {
+            "├─ "
+        };
+        push_tree(child, ctx, &child_prefix, connector, lines);
+    }
+}
+
+fn push_steps(expr: &Expr, ctx: &Context, lines: &mut Vec<String>) {
+    if is_leaf(expr) {
+        return;
+    }
+    for child in children(expr) {
+        push_steps(child, ctx, lines);
+    }
+    lines.push(format!(
+        "{}. {} → {}",
+        lines.len() + 1,
+        reduced_text(expr, ctx),
+        value_text(expr, ctx)
+    ));
+}
+
+/// 式の読み方と計算の手順を、コードブロックに入れる前のテキストにする
+pub fn render(expr: &Expr, trace: Trace, ctx: &Context) -> String {
+    let mut lines = Vec::new();
+    match trace {
+        Trace::Tree => push_tree(expr, ctx, "", "", &mut lines),
+        Trace::Steps => push_steps(expr, ctx, &mut lines),
+    }
+    if lines.is_empty() {
+        return "(計算の手順はありません)".to_string();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::evaluator::{Environment, Mode};
+    use super::super::parser::parse;
+    use super::super::tokenizer::tokenize;
+    use super::*;
+
+    fn trace(input: &str, trace: Trace) -> String {
+        let expr = parse(tokenize(input).unwrap(), input.len() + 1).unwrap();
+        let ctx = Context {
+            mode: Mode::Rational,
+            now: chrono::Utc::now(),
+            env: &Environment::new(),
+        };
+        render(&expr, trace, &ctx)
+    }
+
+    #[test]
+    fn test_steps() {
+        assert_eq!(
+            trace("2 + 3 * 4", Trace::Steps),
+            "1. 3 * 4 → 12\n2. 2 + 12 → 14"
+        );
+        assert_eq!(
+            trace("(2 + 3) * 4 - 1", Trace::Steps),
+            "1. 2 + 3 → 5\n2. 5 * 4 → 20\n3. 20 - 1 → 19"
+        );
+        assert_eq!(
+            trace("-2^2 + 1/3", Trace::Steps),
+            "1. 2 ^ 2 → 4\n2. -(4) → -4\n3. 1 / 3 → 1/3\n4. (-4) + (1/3) → -11/3"
+        );
+        assert_eq!(
+            trace("max(1, 2 * 3)!", Trace::Steps),
+            "1. 2 * 3 → 6\n2. max(1, 6) → 6\n3. 6! → 720"
+        );
+        assert_eq!(
+            trace("1km + 500m in km", Trace::Steps),
+            "1. 1 * km → 1000 m\n2. 500 * m → 500 m\n3. (1000 m) + (500 m) → 1500 m\n4. (1500 m) in km → 1.5 km"
+        );
+        assert_eq!(
+            trace("percentile([1, 2 + 2, 3], 50)", Trace::Steps),
+            "1. 2 + 2 → 4\n2. [1, 4, 3] → [1, 4, 3]\n3. percentile([1, 4, 3], 50) → 3"
+        );
+        assert_eq!(trace("-42", Trace::Steps), "(計算の手順はありません)");
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(
+            trace("2 + 3 * 4", Trace::Tree),
+            "+ → 14\n├─ 2\n└─ * → 12\n   ├─ 3\n   └─ 4"
+        );
+        assert_eq!(
+            trace("(1 | 2) << -1 + 3", Trace::Tree),
+            [
+                "<< → 12",
+                "├─ | → 3",
+                "│  ├─ 1",
+                "│  └─ 2",
+                "└─ + → 2",
+                "   ├─ -1",
+                "   └─ 3",
+            ]
+            .join("\n")
+        );
+        assert_eq!(trace("pi", Trace::Tree), "pi → 3.141592653589793");
+    }
+}
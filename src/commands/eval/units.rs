@@ -0,0 +1,219 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::number::Rational;
+
+// 基本単位ごとの指数。長さ (m)、質量 (kg)、時間 (s)、温度 (K)、データ量 (B) の順
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Dimension([i8; 5]);
+
+const BASE_SYMBOLS: [&str; 5] = ["m", "kg", "s", "K", "B"];
+
+const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0]);
+const MASS: Dimension = Dimension([0, 1, 0, 0, 0]);
+const TIME: Dimension = Dimension([0, 0, 1, 0, 0]);
+const TEMPERATURE: Dimension = Dimension([0, 0, 0, 1, 0]);
+const DATA: Dimension = Dimension([0, 0, 0, 0, 1]);
+
+impl Dimension {
+    pub fn none() -> Self {
+        Dimension::default()
+    }
+
+    pub fn time() -> Self {
+        TIME
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.0.iter().all(|&e| e == 0)
+    }
+
+    fn combine(&self, other: &Dimension, f: impl Fn(i8, i8) -> Option<i8>) -> Option<Self> {
+        let mut out = [0i8; 5];
+        for (i, e) in out.iter_mut().enumerate() {
+            *e = f(self.0[i], other.0[i])?;
+        }
+        Some(Dimension(out))
+    }
+
+    pub fn mul(&self, other: &Dimension) -> Option<Self> {
+        self.combine(other, |a, b| a.checked_add(b))
+    }
+
+    pub fn div(&self, other: &Dimension) -> Option<Self> {
+        self.combine(other, |a, b| a.checked_sub(b))
+    }
+
+    pub fn pow(&self, n: i64) -> Option<Self> {
+        let n = i8::try_from(n).ok()?;
+        self.combine(&Dimension::none(), |a, _| a.checked_mul(n))
+    }
+
+    /// 全ての指数が偶数のときだけ平方根をとれる
+    pub fn sqrt(&self) -> Option<Self> {
+        self.combine(&Dimension::none(), |a, _| {
+            if a % 2 == 0 {
+                Some(a / 2)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// m^2*kg/s^2 のように正の指数を分子に、負の指数を分母に並べる
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let term = |symbol: &str, e: i8| {
+            if e == 1 {
+                symbol.to_string()
+            } else {
+                format!("{}^{}", symbol, e)
+            }
+        };
+        let numerator = BASE_SYMBOLS
+            .iter()
+            .zip(self.0.iter())
+            .filter(|(_, &e)| e > 0)
+            .map(|(symbol, &e)| term(symbol, e))
+            .collect::<Vec<_>>();
+        let denominator = BASE_SYMBOLS
+            .iter()
+            .zip(self.0.iter())
+            .filter(|(_, &e)| e < 0)
+            .map(|(symbol, &e)| term(symbol, -e))
+            .collect::<Vec<_>>();
+
+        if numerator.is_empty() && denominator.is_empty() {
+            return write!(f, "(無次元)");
+        }
+        if numerator.is_empty() {
+            write!(f, "1")?;
+        } else {
+            write!(f, "{}", numerator.join("*"))?;
+        }
+        for term in denominator {
+            write!(f, "/{}", term)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Unit {
+    pub dimension: Dimension,
+    // 基本単位でいくつ分か
+    pub factor: Rational,
+    // 摂氏や華氏のように 0 の位置がずれている単位の、0 度が基本単位でいくつか
+    pub offset: Option<Rational>,
+}
+
+// (名前, 次元, 基本単位でいくつ分か, 0 度の位置)。値は 10 進数か分数で書く
+const UNITS: [(&str, Dimension, &str, Option<&str>); 48] = [
+    ("m", LENGTH, "1", None),
+    ("km", LENGTH, "1000", None),
+    ("cm", LENGTH, "0.01", None),
+    ("mm", LENGTH, "0.001", None),
+    ("um", LENGTH, "0.000001", None),
+    ("nm", LENGTH, "0.000000001", None),
+    ("inch", LENGTH, "0.0254", None),
+    ("ft", LENGTH, "0.3048", None),
+    ("yd", LENGTH, "0.9144", None),
+    ("mi", LENGTH, "1609.344", None),
+    ("kg", MASS, "1", None),
+    ("g", MASS, "0.001", None),
+    ("mg", MASS, "0.000001", None),
+    ("t", MASS, "1000", None),
+    ("lb", MASS, "0.45359237", None),
+    ("oz", MASS, "0.028349523125", None),
+    ("s", TIME, "1", None),
+    ("ms", TIME, "0.001", None),
+    ("us", TIME, "0.000001", None),
+    ("ns", TIME, "0.000000001", None),
+    ("min", TIME, "60", None),
+    ("h", TIME, "3600", None),
+    ("d", TIME, "86400", None),
+    ("week", TIME, "604800", None),
+    ("K", TEMPERATURE, "1", None),
+    ("C", TEMPERATURE, "1", Some("273.15")),
+    ("F", TEMPERATURE, "5/9", Some("45967/180")),
+    ("B", DATA, "1", None),
+    ("bit", DATA, "1/8", None),
+    ("kB", DATA, "1000", None),
+    ("KB", DATA, "1000", None),
+    ("MB", DATA, "1000000", None),
+    ("GB", DATA, "1000000000", None),
+    ("TB", DATA, "1000000000000", None),
+    ("PB", DATA, "1000000000000000", None),
+    ("KiB", DATA, "1024", None),
+    ("MiB", DATA, "1048576", None),
+    ("GiB", DATA, "1073741824", None),
+    ("TiB", DATA, "1099511627776", None),
+    ("PiB", DATA, "1125899906842624", None),
+    ("kbit", DATA, "125", None),
+    ("Kbit", DATA, "125", None),
+    ("Mbit", DATA, "125000", None),
+    ("Gbit", DATA, "125000000", None),
+    ("Tbit", DATA, "125000000000", None),
+    ("Kibit", DATA, "128", None),
+    ("Mibit", DATA, "131072", None),
+    ("Gibit", DATA, "134217728", None),
+];
+
+fn parse_factor(text: &str) -> Option<Rational> {
+    match text.split_once('/') {
+        Some((num, den)) => Rational::parse_decimal(num)?.div(&Rational::parse_decimal(den)?),
+        None => Rational::parse_decimal(text),
+    }
+}
+
+pub fn lookup(name: &str) -> Option<Unit> {
+    let (_, dimension, factor, offset) = UNITS.iter().find(|(n, ..)| *n == name)?;
+    Some(Unit {
+        dimension: *dimension,
+        factor: parse_factor(factor)?,
+        offset: match offset {
+            Some(offset) => Some(parse_factor(offset)?),
+            None => None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimension_display() {
+        let speed = LENGTH.div(&TIME).unwrap();
+        assert_eq!(speed.to_string(), "m/s");
+        let energy = MASS
+            .mul(&LENGTH.pow(2).unwrap())
+            .unwrap()
+            .div(&TIME.pow(2).unwrap())
+            .unwrap();
+        assert_eq!(energy.to_string(), "m^2*kg/s^2");
+        assert_eq!(Dimension::none().div(&TIME).unwrap().to_string(), "1/s");
+        assert_eq!(Dimension::none().to_string(), "(無次元)");
+        assert_eq!(LENGTH.pow(2).unwrap().sqrt(), Some(LENGTH));
+        assert_eq!(LENGTH.sqrt(), None);
+    }
+
+    #[test]
+    fn test_lookup() {
+        // 全ての単位の係数が読めること
+        for (name, ..) in UNITS.iter() {
+            assert!(lookup(name).is_some(), "{}", name);
+        }
+        let f = lookup("F").unwrap();
+        assert_eq!(
+            f.factor,
+            Rational::parse_decimal("5")
+                .unwrap()
+                .div(&Rational::from_i64(9))
+                .unwrap()
+        );
+        assert_eq!(lookup("GiB").unwrap().factor, Rational::from_i64(1 << 30));
+        assert_eq!(lookup("parsec"), None);
+    }
+}
@@ -8,7 +8,13 @@ use serenity::model::prelude::interaction::application_command::{
 
 use serenity::prelude::Context;
 
-use crate::utils::get_db_channel::get_db_channel;
+use crate::store;
+use crate::url::url::Url;
+
+// 登録済みのリンクと比較するための正規形
+fn canonical_link(link: &str) -> String {
+    Url::parse(link).canonicalize(true).to_string()
+}
 
 pub async fn run(options: &[CommandDataOption], ctx: &Context) -> String {
     let operation = match options.iter().find(|option| option.name == "operation") {
@@ -33,51 +39,31 @@ pub async fn run(options: &[CommandDataOption], ctx: &Context) -> String {
         None => "",
     };
 
-    let db_channel = match get_db_channel(ctx).await {
-        Ok(db_channel) => db_channel,
-        Err(why) => return why.to_string(),
-    };
-
-    let messages = match db_channel
-        .messages(&ctx.http, |retriever| retriever.limit(100))
-        .await
-    {
-        Ok(messages) => messages
-            .into_iter()
-            .filter(|message| message.content.starts_with("rss_link"))
-            .collect::<Vec<_>>(),
+    let store = store::get(ctx).await;
+    let links = match store.rss_links().await {
+        Ok(links) => links,
         Err(_) => return "リンクの取得に失敗しました".to_string(),
     };
 
     match operation.as_str() {
         "add" => {
-            // 重複チェック
-            if messages
-                .iter()
-                .any(|x| x.content.split_whitespace().nth(1).unwrap() == link)
-            {
+            // 重複チェック。表記ゆれを吸収するために正規形で比較し、正規形で保存する
+            let canonical = canonical_link(link);
+            if links.iter().any(|x| canonical_link(x) == canonical) {
                 return format!("{} は既に登録されています。", link);
             }
 
-            let _ = match db_channel
-                .id
-                .say(&ctx.http, format!("rss_link {}", link))
-                .await
-            {
-                Ok(_) => "",
-                Err(_) => return "リンクの登録に失敗しました。".to_string(),
-            };
+            if store.add_rss_link(canonical).await.is_err() {
+                return "リンクの登録に失敗しました。".to_string();
+            }
 
             return format!("{} を追加しました。", link);
         }
         "rm" => {
-            match messages
-                .iter()
-                .position(|x| x.content.split_whitespace().nth(1).unwrap() == link)
-            {
-                Some(index) => {
-                    let link_id = messages[index].id;
-                    match db_channel.id.delete_message(&ctx.http, link_id).await {
+            let canonical = canonical_link(link);
+            match links.iter().find(|x| canonical_link(x) == canonical) {
+                Some(stored) => {
+                    match store.remove_rss_link(stored).await {
                         Ok(_) => (),
                         Err(_) => return "リンクの削除に失敗しました".to_string(),
                     }
@@ -89,15 +75,15 @@ pub async fn run(options: &[CommandDataOption], ctx: &Context) -> String {
             }
         }
         "ls" => {
-            if messages.is_empty() {
+            if links.is_empty() {
                 return "RSSが登録されていません。".to_string();
             } else {
                 return format!(
                     "rss list は以下の通りです:
 - {}",
-                    messages
+                    links
                         .iter()
-                        .map(|x| format!("{}", x.content.split_whitespace().nth(1).unwrap()))
+                        .map(|x| Url::parse(x).to_unicode_string())
                         .collect::<Vec<_>>()
                         .join("\n- ")
                 );
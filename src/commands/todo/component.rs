@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serenity::builder::CreateComponents;
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::Context;
+
+use crate::store::{self, Todo};
+use crate::utils::datetime::TimeZone;
+
+use super::list::{base36, Page, Query, PAGE_SIZE};
+use super::response::Response;
+use super::{add, due, edit, mentioned_users, parse_labels, remove, set_done, time_zone};
+
+// select menu の選択肢は 100 文字まで
+const MAX_LABEL_CHARS: usize = 100;
+
+// 一覧で表示しているページと条件
+#[derive(Clone, PartialEq, Debug)]
+pub struct View {
+    // 0 始まり
+    pub index: usize,
+    pub query: Query,
+}
+
+// ボタンやフォームで行う操作
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Prev,
+    Next,
+    Select,
+    Add,
+    Done(u64),
+    Reopen(u64),
+    Edit(u64),
+    Remove(u64),
+}
+
+// custom_id は `todo:<操作>[:<id>][:<ページ>:<条件>]`。bot を再起動しても押せるよう、表示している条件をすべて入れる。
+// 数は 36 進数で書き、タグの長さも抑えて、どの値でも 100 文字に収める
+fn encode(action: Action, view: Option<&View>) -> String {
+    let action = match action {
+        Action::Prev => "prev".to_string(),
+        Action::Next => "next".to_string(),
+        Action::Select => "select".to_string(),
+        Action::Add => "add".to_string(),
+        Action::Done(id) => format!("done:{}", base36(id)),
+        Action::Reopen(id) => format!("reopen:{}", base36(id)),
+        Action::Edit(id) => format!("edit:{}", base36(id)),
+        Action::Remove(id) => format!("rm:{}", base36(id)),
+    };
+    match view {
+        Some(view) => format!(
+            "todo:{}:{}:{}",
+            action,
+            base36(view.index as u64),
+            view.query.encode()
+        ),
+        None => format!("todo:{}", action),
+    }
+}
+
+fn decode(custom_id: &str) -> Option<(Action, Option<View>)> {
+    let rest = custom_id.strip_prefix("todo:")?;
+    let (name, rest) = rest.split_once(':').unwrap_or((rest, ""));
+    let (action, rest) = match name {
+        "prev" => (Action::Prev, rest),
+        "next" => (Action::Next, rest),
+        "select" => (Action::Select, rest),
+        "add" => (Action::Add, rest),
+        "done" | "reopen" | "edit" | "rm" => {
+            let (id, rest) = rest.split_once(':').unwrap_or((rest, ""));
+            let id = u64::from_str_radix(id, 36).ok()?;
+            let action = match name {
+                "done" => Action::Done(id),
+                "reopen" => Action::Reopen(id),
+                "edit" => Action::Edit(id),
+                _ => Action::Remove(id),
+            };
+            (action, rest)
+        }
+        _ => return None,
+    };
+    let view = match rest {
+        "" => None,
+        rest => {
+            let (index, query) = rest.split_once(':')?;
+            Some(View {
+                index: usize::from_str_radix(index, 36).ok()?,
+                query: Query::decode(query)?,
+            })
+        }
+    };
+    Some((action, view))
+}
+
+/// 一覧の 1 ページを embed とボタンで表示する。selected の todo がこのページにあれば、完了や削除のボタンも出す
+pub fn list(
+    todos: &[Todo],
+    view: &View,
+    selected: Option<u64>,
+    user_id: u64,
+    now: i64,
+) -> Response {
+    let matched = view.query.apply(todos, user_id);
+    if matched.is_empty() {
+        return view.query.empty_message().into();
+    }
+    let page = Page::new(&matched, view.index, now);
+    // 範囲外のページは最後のページになるので、ボタンもそれに合わせる
+    let view = View {
+        index: page.index,
+        query: view.query.clone(),
+    };
+    let shown = matched
+        .iter()
+        .skip(page.index * PAGE_SIZE)
+        .take(page.lines.len())
+        .collect::<Vec<_>>();
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(encode(Action::Select, Some(&view)))
+                .placeholder("操作する TODO を選ぶ")
+                .options(|options| {
+                    for todo in &shown {
+                        options.create_option(|option| {
+                            let label = format!("{} {}", todo.id, todo.text);
+                            option
+                                .label(label.chars().take(MAX_LABEL_CHARS).collect::<String>())
+                                .value(todo.id)
+                                .default_selection(selected == Some(todo.id))
+                        });
+                    }
+                    options
+                })
+        })
+    });
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(encode(Action::Prev, Some(&view)))
+                .label("前へ")
+                .style(ButtonStyle::Secondary)
+                .disabled(page.index == 0)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(encode(Action::Next, Some(&view)))
+                .label("次へ")
+                .style(ButtonStyle::Secondary)
+                .disabled(page.index + 1 >= page.count)
+        })
+    });
+    if let Some(todo) = shown.iter().find(|todo| Some(todo.id) == selected) {
+        components.create_action_row(|row| {
+            match todo.done {
+                None => row.create_button(|button| {
+                    button
+                        .custom_id(encode(Action::Done(todo.id), Some(&view)))
+                        .label("完了")
+                        .style(ButtonStyle::Success)
+                }),
+                Some(_) => row.create_button(|button| {
+                    button
+                        .custom_id(encode(Action::Reopen(todo.id), Some(&view)))
+                        .label("未完了に戻す")
+                        .style(ButtonStyle::Secondary)
+                }),
+            };
+            row.create_button(|button| {
+                button
+                    .custom_id(encode(Action::Edit(todo.id), Some(&view)))
+                    .label("編集")
+                    .style(ButtonStyle::Primary)
+            })
+            .create_button(|button| {
+                button
+                    .custom_id(encode(Action::Remove(todo.id), Some(&view)))
+                    .label("削除")
+                    .style(ButtonStyle::Danger)
+            })
+        });
+    }
+
+    Response {
+        embed: Some(page.embed()),
+        components,
+        ..Response::from(String::new())
+    }
+}
+
+// 操作の結果を添えて、押された一覧を読み直したものに書き換える
+async fn refresh(
+    ctx: &Context,
+    view: &View,
+    selected: Option<u64>,
+    user_id: u64,
+    content: Option<String>,
+) -> Response {
+    let todos = match store::get(ctx).await.todos().await {
+        Ok(todos) => todos,
+        Err(_) => return "メッセージの取得に失敗しました".into(),
+    };
+    let mut response = list(&todos, view, selected, user_id, Utc::now().timestamp());
+    if let Some(content) = content {
+        // 一覧が空になったときは、その表示の前に結果を出す
+        response.content = match response.embed {
+            Some(_) => content,
+            None => format!("{}\n{}", content, response.content),
+        };
+    }
+    response.update()
+}
+
+// フォームの本文。parse_labels で読み直せる形にする
+fn format_labels(todo: &Todo) -> String {
+    let mut text = todo.text.clone();
+    for tag in &todo.tags {
+        text.push_str(&format!(" #{}", tag));
+    }
+    if let Some(priority) = todo.priority {
+        text.push_str(&format!(" p{}", priority));
+    }
+    text
+}
+
+/// add と edit のフォーム。edit では今の本文、タグ、優先度、期限を入れておく
+pub fn form(todo: Option<&Todo>, view: Option<&View>, time_zone: &TimeZone) -> Response {
+    let (custom_id, title) = match todo {
+        Some(todo) => (
+            encode(Action::Edit(todo.id), view),
+            format!("TODO {} を編集", todo.id),
+        ),
+        None => (encode(Action::Add, view), "TODO を追加".to_string()),
+    };
+    let message = todo.map(format_labels);
+    let due = todo
+        .and_then(|todo| todo.due)
+        .and_then(|due| DateTime::from_timestamp(due, 0))
+        .map(|due| {
+            due.with_timezone(&time_zone.fixed_offset())
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        });
+
+    let mut components = CreateComponents::default();
+    components
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("message")
+                    .label("内容 (#tag や p1〜p3 も書けます)")
+                    .style(InputTextStyle::Paragraph)
+                    .required(true);
+                if let Some(message) = message {
+                    input.value(message);
+                }
+                input
+            })
+        })
+        .create_action_row(|row| {
+            row.create_input_text(|input| {
+                input
+                    .custom_id("due")
+                    .label(format!("期限 ({}、空なら期限なし)", time_zone.name))
+                    .placeholder("tomorrow 10:00, in 3d, 2026-10-20 18:00")
+                    .style(InputTextStyle::Short)
+                    .required(false);
+                if let Some(due) = due {
+                    input.value(due);
+                }
+                input
+            })
+        });
+
+    Response {
+        kind: InteractionResponseType::Modal,
+        components,
+        modal: Some((custom_id, title)),
+        ..Response::from(String::new())
+    }
+}
+
+/// 一覧のボタンと select menu
+pub async fn component(interaction: &MessageComponentInteraction, ctx: &Context) -> Response {
+    let user = &interaction.user;
+    let (action, view) = match decode(&interaction.data.custom_id) {
+        Some((action, Some(view))) => (action, view),
+        _ => return "ボタンが不正です".into(),
+    };
+
+    let (index, id) = match action {
+        Action::Prev => {
+            let view = View {
+                index: view.index.saturating_sub(1),
+                ..view
+            };
+            return refresh(ctx, &view, None, user.id.0, None).await;
+        }
+        Action::Next => {
+            let view = View {
+                index: view.index + 1,
+                ..view
+            };
+            return refresh(ctx, &view, None, user.id.0, None).await;
+        }
+        Action::Select => {
+            let selected = interaction
+                .data
+                .values
+                .first()
+                .and_then(|value| value.parse().ok());
+            return refresh(ctx, &view, selected, user.id.0, None).await;
+        }
+        Action::Add => return "ボタンが不正です".into(),
+        Action::Done(id) | Action::Reopen(id) | Action::Edit(id) | Action::Remove(id) => {
+            (view.index, id)
+        }
+    };
+
+    let todos = match store::get(ctx).await.todos().await {
+        Ok(todos) => todos,
+        Err(_) => return "メッセージの取得に失敗しました".into(),
+    };
+    let todo = match todos.iter().find(|todo| todo.id == id) {
+        Some(todo) => todo,
+        None => return format!("{} は存在しません。", id).into(),
+    };
+    let now = Utc::now().timestamp();
+    let content = match action {
+        Action::Edit(_) => return form(Some(todo), Some(&view), &time_zone(ctx, user.id).await),
+        Action::Remove(_) => remove(ctx, user, todo).await,
+        _ => set_done(ctx, user, todo, action == Action::Done(id), now).await,
+    };
+    let view = View { index, ..view };
+    refresh(ctx, &view, Some(id), user.id.0, Some(content)).await
+}
+
+/// add と edit のフォームの送信。一覧から開いたフォームなら一覧を書き換える
+pub async fn modal_submit(interaction: &ModalSubmitInteraction, ctx: &Context) -> Response {
+    let user = &interaction.user;
+    let now = Utc::now();
+    let (action, view) = match decode(&interaction.data.custom_id) {
+        Some(decoded) => decoded,
+        None => return "フォームが不正です".into(),
+    };
+    let values = interaction
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(input) => {
+                Some((input.custom_id.as_str(), input.value.as_str()))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let message = values.get("message").copied().unwrap_or_default();
+
+    // フォームには今の期限を入れてあるので、空なら期限をなくす
+    let due = match values.get("due").map(|due| due.trim()).unwrap_or_default() {
+        "" => None,
+        text => match due::parse(text, now, &time_zone(ctx, user.id).await) {
+            Ok(due) => Some(due),
+            Err(message) => return message.into(),
+        },
+    };
+    let (text, tags, priority) = parse_labels(message);
+    if text.is_empty() {
+        return "本文がありません".into();
+    }
+    let mut assignees = Vec::new();
+    for id in mentioned_users(message) {
+        if !assignees.contains(&id) {
+            assignees.push(id);
+        }
+    }
+
+    match action {
+        Action::Add => {
+            let todo = Todo {
+                creator: Some(user.id.0),
+                assignees,
+                due,
+                channel: Some(interaction.channel_id.0),
+                tags,
+                priority,
+                ..Todo::new(0, text)
+            };
+            add(ctx, user, todo, now.timestamp()).await.into()
+        }
+        Action::Edit(id) => {
+            let todos = match store::get(ctx).await.todos().await {
+                Ok(todos) => todos,
+                Err(_) => return "メッセージの取得に失敗しました".into(),
+            };
+            let todo = match todos.iter().find(|todo| todo.id == id) {
+                Some(todo) => todo,
+                None => return format!("{} は存在しません。", id).into(),
+            };
+            // 本文、タグ、優先度、期限はフォームの内容に置き換え、担当者は本文のメンションを足す
+            let mut edited = Todo {
+                text,
+                tags,
+                priority,
+                due,
+                // 期限が変わったら改めて通知する
+                reminded: todo.reminded && todo.due == due,
+                ..todo.clone()
+            };
+            for id in assignees {
+                if !edited.assignees.contains(&id) {
+                    edited.assignees.push(id);
+                }
+            }
+            let content = edit(ctx, user, todo, edited, now.timestamp()).await;
+            match (view, &interaction.message) {
+                (Some(view), Some(_)) => {
+                    refresh(ctx, &view, Some(id), user.id.0, Some(content)).await
+                }
+                _ => content.into(),
+            }
+        }
+        _ => "フォームが不正です".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::todo::list::{Filter, Sort};
+    use crate::commands::todo::MAX_TAG_CHARS;
+
+    #[test]
+    fn test_custom_id() {
+        let view = View {
+            index: 2,
+            query: Query {
+                filter: Filter::Open,
+                tag: Some("bug".to_string()),
+                priority: None,
+                assignee: None,
+                due_before: None,
+                sort: Sort::Priority,
+            },
+        };
+        let actions = [
+            Action::Prev,
+            Action::Next,
+            Action::Select,
+            Action::Done(3),
+            Action::Reopen(3),
+            Action::Edit(3),
+            Action::Remove(3),
+        ];
+        for action in actions {
+            let custom_id = encode(action, Some(&view));
+            assert_eq!(decode(&custom_id), Some((action, Some(view.clone()))));
+        }
+        assert_eq!(
+            encode(Action::Remove(3), Some(&view)),
+            "todo:rm:3:2:o:p::::bug"
+        );
+
+        // どの条件も最も長くなる値でも 100 文字に収まる
+        let view = View {
+            index: usize::MAX,
+            query: Query {
+                filter: Filter::Mine,
+                tag: Some("あ".repeat(MAX_TAG_CHARS)),
+                priority: Some(3),
+                assignee: Some(u64::MAX),
+                due_before: Some(i64::MIN),
+                sort: Sort::Priority,
+            },
+        };
+        for action in [Action::Select, Action::Reopen(u64::MAX)] {
+            let custom_id = encode(action, Some(&view));
+            assert!(custom_id.chars().count() <= 100, "{}", custom_id);
+            assert_eq!(decode(&custom_id), Some((action, Some(view.clone()))));
+        }
+        assert_eq!(encode(Action::Add, None), "todo:add");
+        assert_eq!(decode("todo:add"), Some((Action::Add, None)));
+        assert_eq!(decode("todo:edit:c"), Some((Action::Edit(12), None)));
+        assert_eq!(decode("todo:edit"), None);
+        assert_eq!(decode("todo:next:-:o:i::::"), None);
+        assert_eq!(decode("rss:next"), None);
+    }
+
+    #[test]
+    fn test_format_labels() {
+        let todo = Todo {
+            tags: vec!["bug".to_string(), "ui".to_string()],
+            priority: Some(2),
+            ..Todo::new(1, "fix login".to_string())
+        };
+        assert_eq!(format_labels(&todo), "fix login #bug #ui p2");
+        assert_eq!(
+            parse_labels(&format_labels(&todo)),
+            (todo.text.clone(), todo.tags.clone(), todo.priority)
+        );
+    }
+}
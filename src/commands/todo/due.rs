@@ -0,0 +1,187 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone as _, Utc, Weekday};
+
+use crate::utils::datetime::TimeZone;
+
+// 日付だけで時刻がなければ、その日の終わりを期限にする
+const END_OF_DAY: (u32, u32) = (23, 59);
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+// `in 3d 2h` の単位と秒数
+const UNITS: [(&str, i64); 11] = [
+    ("w", 7 * 86400),
+    ("week", 7 * 86400),
+    ("weeks", 7 * 86400),
+    ("d", 86400),
+    ("day", 86400),
+    ("days", 86400),
+    ("h", 3600),
+    ("hour", 3600),
+    ("hours", 3600),
+    ("min", 60),
+    ("m", 60),
+];
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(word, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(word, "%H:%M:%S"))
+        .ok()
+}
+
+// `3d` や `30min` を秒数にする
+fn parse_amount(word: &str) -> Option<i64> {
+    let digits = word.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = word[..digits].parse().ok()?;
+    let (_, size) = UNITS.iter().find(|(unit, _)| *unit == &word[digits..])?;
+    amount.checked_mul(*size)
+}
+
+fn relative(words: &[&str]) -> Option<i64> {
+    if words.is_empty() {
+        return None;
+    }
+    words
+        .iter()
+        .try_fold(0i64, |total, word| total.checked_add(parse_amount(word)?))
+}
+
+// 今日から見て次のその曜日。今日と同じ曜日なら 1 週間後
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64
+        - 1)
+        % 7
+        + 1;
+    today + Duration::days(days)
+}
+
+/// `tomorrow 10:00`, `in 3d`, `friday`, `2026-10-20 18:00` のような期限を time_zone の時刻として読み、
+/// UNIX 時間の秒数を返す
+pub fn parse(input: &str, now: DateTime<Utc>, time_zone: &TimeZone) -> Result<i64, String> {
+    let mut input = input.trim().to_lowercase();
+    // 2026-10-20T18:00 の T は日付と時刻の区切り
+    if input.as_bytes().get(10) == Some(&b't') && input.as_bytes()[..10].is_ascii() {
+        input.replace_range(10..11, " ");
+    }
+    let words = input.split_whitespace().collect::<Vec<_>>();
+    let error = || format!("期限 {} を読めません", input);
+
+    if let Some((&"in", amounts)) = words.split_first() {
+        let seconds = relative(amounts).ok_or_else(error)?;
+        return now.timestamp().checked_add(seconds).ok_or_else(error);
+    }
+
+    let offset = time_zone.fixed_offset();
+    let today = now.with_timezone(&offset).date_naive();
+    let (date, rest) = match words.split_first() {
+        Some((&word, rest)) => {
+            let date = match word {
+                "today" | "今日" => Some(today),
+                "tomorrow" | "明日" => Some(today + Duration::days(1)),
+                "明後日" => Some(today + Duration::days(2)),
+                _ => WEEKDAYS
+                    .iter()
+                    .find(|(name, _)| *name == word || name[..3] == *word)
+                    .map(|(_, weekday)| next_weekday(today, *weekday))
+                    .or_else(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()),
+            };
+            match date {
+                Some(date) => (Some(date), rest),
+                None => (None, &words[..]),
+            }
+        }
+        None => return Err("期限が空です".to_string()),
+    };
+    let time = match rest {
+        [] => None,
+        [word] => Some(parse_time(word).ok_or_else(error)?),
+        _ => return Err(error()),
+    };
+
+    let local = match (date, time) {
+        (Some(date), Some(time)) => date.and_time(time),
+        (Some(date), None) => date
+            .and_hms_opt(END_OF_DAY.0, END_OF_DAY.1, 0)
+            .ok_or_else(error)?,
+        // 時刻だけなら次にその時刻になるとき
+        (None, Some(time)) => {
+            let local = today.and_time(time);
+            match offset.from_local_datetime(&local).single() {
+                Some(datetime) if datetime.timestamp() <= now.timestamp() => {
+                    local + Duration::days(1)
+                }
+                _ => local,
+            }
+        }
+        (None, None) => return Err(error()),
+    };
+    offset
+        .from_local_datetime(&local)
+        .single()
+        .map(|datetime| datetime.timestamp())
+        .ok_or_else(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::datetime::parse_literal;
+
+    // 2026-10-18 (Sun) 09:30 JST
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(parse_literal("2026-10-18T09:30").unwrap(), 0).unwrap()
+    }
+
+    fn jst(input: &str) -> Result<i64, String> {
+        parse(input, now(), &TimeZone::jst())
+    }
+
+    fn at(literal: &str) -> Result<i64, String> {
+        Ok(parse_literal(literal).unwrap())
+    }
+
+    #[test]
+    fn test_parse_due() {
+        assert_eq!(jst("tomorrow 10:00"), at("2026-10-19T10:00"));
+        assert_eq!(jst("Tomorrow"), at("2026-10-19T23:59"));
+        assert_eq!(jst("明日 8:15"), at("2026-10-19T08:15"));
+        assert_eq!(jst("today 18:00"), at("2026-10-18T18:00"));
+        assert_eq!(jst("in 3d"), Ok(now().timestamp() + 3 * 86400));
+        assert_eq!(jst("in 1d 2h 30min"), Ok(now().timestamp() + 95400));
+        assert_eq!(jst("friday"), at("2026-10-23T23:59"));
+        assert_eq!(jst("sun 12:00"), at("2026-10-25T12:00"));
+        assert_eq!(jst("2026-10-20 18:00"), at("2026-10-20T18:00"));
+        assert_eq!(jst("2026-10-20T18:00"), at("2026-10-20T18:00"));
+        assert_eq!(jst("2026-10-20"), at("2026-10-20T23:59"));
+        // 過ぎた時刻は翌日
+        assert_eq!(jst("10:00"), at("2026-10-18T10:00"));
+        assert_eq!(jst("9:00"), at("2026-10-19T09:00"));
+    }
+
+    #[test]
+    fn test_parse_due_time_zone() {
+        // PST ではまだ 10/17 なので、today 17:30 は JST の 10/18 10:30
+        let pst = TimeZone::find("PST").unwrap();
+        assert_eq!(parse("today 17:30", now(), &pst), at("2026-10-18T10:30"));
+        assert_eq!(parse("today", now(), &pst), at("2026-10-18T16:59"));
+    }
+
+    #[test]
+    fn test_parse_due_error() {
+        assert!(jst("").is_err());
+        assert!(jst("in").is_err());
+        assert!(jst("in 3x").is_err());
+        assert!(jst("tomorrow 25:00").is_err());
+        assert!(jst("tomorrow 10:00 pm").is_err());
+        assert!(jst("someday").is_err());
+        assert!(jst("2026-02-30").is_err());
+    }
+}
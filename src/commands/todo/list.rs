@@ -0,0 +1,483 @@
+use serenity::builder::CreateEmbed;
+use serenity::utils::colours;
+
+use crate::store::Todo;
+
+// 1 ページに並べる todo の数
+pub const PAGE_SIZE: usize = 10;
+// embed の説明は 4096 文字までなので、1 件ごとに切り詰めて 1 ページに収める
+const MAX_LINE_CHARS: usize = 300;
+
+// 36 進数。u64 でも 13 文字に収まる
+pub fn base36(mut n: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit((n % 36) as u32, 36).unwrap_or('0'));
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+// ls で表示する todo
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Filter {
+    Open,
+    Done,
+    All,
+    // 自分が作ったか担当している未完了の todo
+    Mine,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "open" => Some(Filter::Open),
+            "done" => Some(Filter::Done),
+            "all" => Some(Filter::All),
+            "mine" => Some(Filter::Mine),
+            _ => None,
+        }
+    }
+
+    // custom_id に入れる 1 文字
+    fn code(&self) -> char {
+        match self {
+            Filter::Open => 'o',
+            Filter::Done => 'd',
+            Filter::All => 'a',
+            Filter::Mine => 'm',
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "o" => Some(Filter::Open),
+            "d" => Some(Filter::Done),
+            "a" => Some(Filter::All),
+            "m" => Some(Filter::Mine),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, todo: &Todo, user_id: u64) -> bool {
+        match self {
+            Filter::Open => todo.done.is_none(),
+            Filter::Done => todo.done.is_some(),
+            Filter::All => true,
+            Filter::Mine => todo.done.is_none() && todo.belongs_to(user_id),
+        }
+    }
+}
+
+// ls の並べ方。どれも最後は id 順
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sort {
+    Id,
+    // p1 が先、優先度のないものは最後
+    Priority,
+    // 期限の近いものが先、期限のないものは最後
+    Due,
+}
+
+impl Sort {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "id" => Some(Sort::Id),
+            "priority" => Some(Sort::Priority),
+            "due" => Some(Sort::Due),
+            _ => None,
+        }
+    }
+
+    // custom_id に入れる 1 文字
+    fn code(&self) -> char {
+        match self {
+            Sort::Id => 'i',
+            Sort::Priority => 'p',
+            Sort::Due => 'd',
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "i" => Some(Sort::Id),
+            "p" => Some(Sort::Priority),
+            "d" => Some(Sort::Due),
+            _ => None,
+        }
+    }
+}
+
+// ls の条件。None の条件では絞り込まない
+#[derive(Clone, PartialEq, Debug)]
+pub struct Query {
+    pub filter: Filter,
+    pub tag: Option<String>,
+    pub priority: Option<u8>,
+    pub assignee: Option<u64>,
+    // この日時までに期限がくるもの
+    pub due_before: Option<i64>,
+    pub sort: Sort,
+}
+
+impl Query {
+    // ボタンの custom_id に入れる形。`o:i:1:16::bug` のように、ない条件は空にする
+    // custom_id は 100 文字までなので、数は 36 進数で短く書く
+    pub fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}",
+            self.filter.code(),
+            self.sort.code(),
+            self.priority.map(|p| p.to_string()).unwrap_or_default(),
+            self.assignee.map(base36).unwrap_or_default(),
+            self.due_before
+                .map(|due| match due < 0 {
+                    true => format!("-{}", base36(due.unsigned_abs())),
+                    false => base36(due as u64),
+                })
+                .unwrap_or_default(),
+            self.tag.clone().unwrap_or_default()
+        )
+    }
+
+    pub fn decode(text: &str) -> Option<Self> {
+        fn parse<T>(value: &str, f: impl Fn(&str) -> Option<T>) -> Option<Option<T>> {
+            match value {
+                "" => Some(None),
+                value => f(value).map(Some),
+            }
+        }
+        // タグは最後なので、: を含んでいてもそのまま残る
+        let mut fields = text.splitn(6, ':');
+        Some(Query {
+            filter: Filter::from_code(fields.next()?)?,
+            sort: Sort::from_code(fields.next()?)?,
+            priority: parse(fields.next()?, |p| p.parse().ok())?,
+            assignee: parse(fields.next()?, |id| u64::from_str_radix(id, 36).ok())?,
+            due_before: parse(fields.next()?, |due| i64::from_str_radix(due, 36).ok())?,
+            tag: parse(fields.next()?, |tag| Some(tag.to_string()))?,
+        })
+    }
+
+    fn matches(&self, todo: &Todo, user_id: u64) -> bool {
+        self.filter.matches(todo, user_id)
+            && self.tag.as_ref().is_none_or(|tag| todo.tags.contains(tag))
+            && self.priority.is_none_or(|p| todo.priority == Some(p))
+            && self.assignee.is_none_or(|id| todo.assignees.contains(&id))
+            && self
+                .due_before
+                .is_none_or(|before| todo.due.is_some_and(|due| due <= before))
+    }
+
+    // 条件に合うものだけ、並べ替えて返す
+    pub fn apply<'a>(&self, todos: &'a [Todo], user_id: u64) -> Vec<&'a Todo> {
+        let mut todos = todos
+            .iter()
+            .filter(|todo| self.matches(todo, user_id))
+            .collect::<Vec<_>>();
+        match self.sort {
+            Sort::Id => todos.sort_by_key(|todo| todo.id),
+            Sort::Priority => todos.sort_by_key(|todo| (todo.priority.unwrap_or(u8::MAX), todo.id)),
+            Sort::Due => todos.sort_by_key(|todo| (todo.due.unwrap_or(i64::MAX), todo.id)),
+        }
+        todos
+    }
+
+    // 1 件もないときの表示
+    pub fn empty_message(&self) -> &'static str {
+        let narrowed = self.tag.is_some()
+            || self.priority.is_some()
+            || self.assignee.is_some()
+            || self.due_before.is_some();
+        match self.filter {
+            _ if narrowed => "条件に合う TODO はありません。",
+            Filter::Done => "完了した TODO はありません。",
+            Filter::Mine => "あなたの TODO はありません。",
+            _ => "TODOリストには何もありません。",
+        }
+    }
+}
+
+fn format_assignees(todo: &Todo) -> String {
+    if todo.assignees.is_empty() {
+        return String::new();
+    }
+    let assignees = todo
+        .assignees
+        .iter()
+        .map(|id| format!("<@{}>", id))
+        .collect::<Vec<_>>();
+    format!(" (担当: {})", assignees.join(", "))
+}
+
+fn format_due(todo: &Todo, now: i64) -> String {
+    match todo.due {
+        Some(due) if todo.is_overdue(now) => format!(" (**期限切れ** <t:{}:f>)", due),
+        Some(due) => format!(" (期限: <t:{}:f>)", due),
+        None => String::new(),
+    }
+}
+
+// `[p1] review #bug` のように、優先度とタグを本文に添える
+fn format_text(todo: &Todo) -> String {
+    let mut text = String::new();
+    if let Some(priority) = todo.priority {
+        text.push_str(&format!("[p{}] ", priority));
+    }
+    text.push_str(&todo.text);
+    for tag in &todo.tags {
+        text.push_str(&format!(" #{}", tag));
+    }
+    text
+}
+
+pub fn format_todo(todo: &Todo, now: i64) -> String {
+    match &todo.done {
+        // <t:..:f> と <@..> は Discord がユーザーの環境に合わせて表示する
+        Some(done) => format!(
+            "~~{} {}~~ (<t:{}:f> <@{}> が完了)",
+            todo.id,
+            format_text(todo),
+            done.at,
+            done.by
+        ),
+        None => format!(
+            "{} {}{}{}",
+            todo.id,
+            format_text(todo),
+            format_assignees(todo),
+            format_due(todo, now)
+        ),
+    }
+}
+
+fn truncate(line: String) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+// ls の 1 ページ分
+#[derive(Clone, PartialEq, Debug)]
+pub struct Page {
+    pub lines: Vec<String>,
+    // 0 始まり
+    pub index: usize,
+    pub count: usize,
+    pub total: usize,
+}
+
+impl Page {
+    /// index が範囲外なら最後のページにする
+    pub fn new(todos: &[&Todo], index: usize, now: i64) -> Self {
+        let count = todos.len().div_ceil(PAGE_SIZE).max(1);
+        let index = index.min(count - 1);
+        let lines = todos
+            .iter()
+            .skip(index * PAGE_SIZE)
+            .take(PAGE_SIZE)
+            .map(|todo| truncate(format_todo(todo, now)))
+            .collect();
+        Page {
+            lines,
+            index,
+            count,
+            total: todos.len(),
+        }
+    }
+
+    pub fn embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("TODOリスト")
+            .description(
+                self.lines
+                    .iter()
+                    .map(|line| format!("・{}", line))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .footer(|f| {
+                f.text(format!(
+                    "{}/{} ページ ({} 件)",
+                    self.index + 1,
+                    self.count,
+                    self.total
+                ))
+            })
+            .color(colours::branding::YELLOW);
+        embed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Completion;
+
+    const NOW: i64 = 1_792_400_000;
+
+    fn query(filter: Filter) -> Query {
+        Query {
+            filter,
+            tag: None,
+            priority: None,
+            assignee: None,
+            due_before: None,
+            sort: Sort::Id,
+        }
+    }
+
+    fn todos() -> Vec<Todo> {
+        vec![
+            Todo {
+                creator: Some(7),
+                due: Some(NOW + 3600),
+                tags: vec!["bug".to_string()],
+                ..Todo::new(3, "review".to_string())
+            },
+            Todo {
+                done: Some(Completion {
+                    at: 1_792_300_000,
+                    by: 42,
+                }),
+                ..Todo::new(1, "牛乳を買う".to_string())
+            },
+            Todo {
+                assignees: vec![7, 42],
+                priority: Some(1),
+                due: Some(NOW + 60),
+                ..Todo::new(2, "deploy".to_string())
+            },
+            Todo {
+                priority: Some(3),
+                tags: vec!["bug".to_string(), "ui".to_string()],
+                ..Todo::new(4, "layout".to_string())
+            },
+        ]
+    }
+
+    fn lines(query: &Query, user_id: u64, now: i64) -> Vec<String> {
+        let todos = todos();
+        let matched = query.apply(&todos, user_id);
+        Page::new(&matched, 0, now).lines
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(
+            lines(&query(Filter::Open), 42, NOW),
+            vec![
+                "2 [p1] deploy (担当: <@7>, <@42>) (期限: <t:1792400060:f>)",
+                "3 review #bug (期限: <t:1792403600:f>)",
+                "4 [p3] layout #bug #ui",
+            ]
+        );
+        assert_eq!(
+            lines(&query(Filter::Mine), 7, NOW + 7200),
+            vec![
+                "2 [p1] deploy (担当: <@7>, <@42>) (**期限切れ** <t:1792400060:f>)",
+                "3 review #bug (**期限切れ** <t:1792403600:f>)",
+            ]
+        );
+        assert_eq!(
+            lines(&query(Filter::Done), 42, NOW),
+            vec!["~~1 牛乳を買う~~ (<t:1792300000:f> <@42> が完了)"]
+        );
+
+        let tagged = Query {
+            tag: Some("bug".to_string()),
+            sort: Sort::Priority,
+            ..query(Filter::Open)
+        };
+        let ids = |query: &Query| {
+            let todos = todos();
+            query
+                .apply(&todos, 42)
+                .iter()
+                .map(|todo| todo.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&tagged), vec![4, 3]);
+        assert_eq!(
+            ids(&Query {
+                sort: Sort::Due,
+                ..query(Filter::All)
+            }),
+            vec![2, 3, 1, 4]
+        );
+        assert_eq!(
+            ids(&Query {
+                due_before: Some(NOW + 600),
+                ..query(Filter::Open)
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            ids(&Query {
+                assignee: Some(7),
+                ..query(Filter::All)
+            }),
+            vec![2]
+        );
+        let none = Query {
+            priority: Some(2),
+            ..query(Filter::Open)
+        };
+        assert_eq!(ids(&none), Vec::<u64>::new());
+        assert_eq!(none.empty_message(), "条件に合う TODO はありません。");
+        assert_eq!(
+            query(Filter::Mine).empty_message(),
+            "あなたの TODO はありません。"
+        );
+    }
+
+    #[test]
+    fn test_query_encode() {
+        let full = Query {
+            tag: Some("a:b".to_string()),
+            priority: Some(2),
+            assignee: Some(42),
+            due_before: Some(NOW),
+            sort: Sort::Due,
+            ..query(Filter::Mine)
+        };
+        assert_eq!(full.encode(), "m:d:2:16:tn5cow:a:b");
+        assert_eq!(Query::decode(&full.encode()), Some(full));
+        let past = Query {
+            due_before: Some(-NOW),
+            ..query(Filter::All)
+        };
+        assert_eq!(past.encode(), "a:i:::-tn5cow:");
+        assert_eq!(Query::decode(&past.encode()), Some(past));
+        assert_eq!(query(Filter::Open).encode(), "o:i::::");
+        assert_eq!(Query::decode("o:i::::"), Some(query(Filter::Open)));
+        assert_eq!(Query::decode("o:i"), None);
+        assert_eq!(Query::decode("open:id::::"), None);
+        assert_eq!(Query::decode("o:i:x:::"), None);
+    }
+
+    #[test]
+    fn test_page() {
+        let todos = (1..=23)
+            .map(|id| Todo::new(id, "x".repeat(id as usize * 20)))
+            .collect::<Vec<_>>();
+        let todos = todos.iter().collect::<Vec<_>>();
+        let page = Page::new(&todos, 1, NOW);
+        assert_eq!((page.index, page.count, page.total), (1, 3, 23));
+        assert_eq!(page.lines.len(), 10);
+        assert!(page.lines[0].starts_with("11 xxx"));
+        // 長い本文は切り詰める
+        assert_eq!(page.lines[9].chars().count(), MAX_LINE_CHARS + 1);
+        assert!(page.lines[9].ends_with('…'));
+
+        // 範囲外なら最後のページ
+        let page = Page::new(&todos, 10, NOW);
+        assert_eq!((page.index, page.lines.len()), (2, 3));
+        assert_eq!(Page::new(&[], 0, NOW).count, 1);
+    }
+}
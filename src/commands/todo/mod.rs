@@ -0,0 +1,871 @@
+extern crate rand;
+
+mod component;
+mod due;
+mod list;
+mod response;
+mod transfer;
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::prelude::{Attachment, AttachmentType, ChannelId, User, UserId};
+
+use serenity::prelude::{Context, TypeMapKey};
+use tracing::error;
+
+use crate::store::{self, Completion, Todo};
+use crate::utils::datetime::TimeZone;
+
+use self::component::View;
+use self::list::{format_todo, Filter, Query, Sort};
+use self::transfer::Format;
+
+pub use self::component::{component, modal_submit};
+pub use self::response::Response;
+
+// ユーザーごとに取り消せる変更の数
+const MAX_HISTORY: usize = 20;
+
+// undo で戻すための変更。追加した todo、消す前の todo、変更前と変更後の todo、import で追加した todo
+#[derive(Clone, PartialEq, Debug)]
+pub enum Change {
+    Added(Todo),
+    Removed(Todo),
+    Updated(Todo, Todo),
+    // import は 1 回の変更としてまとめて取り消す
+    Imported(Vec<Todo>),
+}
+
+// /todo の変更履歴。bot が動いている間だけ、ユーザーごとに新しいものを後ろに保持する
+pub struct History;
+
+impl TypeMapKey for History {
+    type Value = HashMap<UserId, Vec<Change>>;
+}
+
+// 期限を読むときのタイムゾーン。一度指定すると bot が動いている間はユーザーごとに覚えておく
+pub struct TimeZones;
+
+impl TypeMapKey for TimeZones {
+    type Value = HashMap<UserId, TimeZone>;
+}
+
+async fn push_history(ctx: &Context, user_id: UserId, change: Change) {
+    let mut data = ctx.data.write().await;
+    let history = data
+        .entry::<History>()
+        .or_insert_with(HashMap::new)
+        .entry(user_id)
+        .or_insert_with(Vec::new);
+    history.push(change);
+    if history.len() > MAX_HISTORY {
+        history.remove(0);
+    }
+}
+
+async fn pop_history(ctx: &Context, user_id: UserId) -> Option<Change> {
+    let mut data = ctx.data.write().await;
+    data.get_mut::<History>()?.get_mut(&user_id)?.pop()
+}
+
+// ボタンの custom_id (100 文字まで) に入れるので、タグの長さを抑える
+const MAX_TAG_CHARS: usize = 24;
+// import で読み込むファイルの大きさと todo の数。todo ごとに db チャンネルへ投稿するので抑えておく
+const MAX_IMPORT_BYTES: u64 = 1024 * 1024;
+const MAX_IMPORT_TODOS: usize = 100;
+
+fn parse_priority(word: &str) -> Option<u8> {
+    match word.to_lowercase().as_str() {
+        "p1" => Some(1),
+        "p2" => Some(2),
+        "p3" => Some(3),
+        _ => None,
+    }
+}
+
+// # を除いたタグ。使えるのは MAX_TAG_CHARS 文字までの文字と数字、_ と - だけで、小文字にそろえる
+fn parse_tag(tag: &str) -> Option<String> {
+    let re = regex::Regex::new(r"^[\p{L}\p{N}_-]+$").unwrap();
+    (re.is_match(tag) && tag.chars().count() <= MAX_TAG_CHARS).then(|| tag.to_lowercase())
+}
+
+// 本文から #tag と p1〜p3 を取り出し、残りを本文にする。<#id> はチャンネルなのでタグにしない
+fn parse_labels(text: &str) -> (String, Vec<String>, Option<u8>) {
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+    let mut priority = None;
+    for word in text.split_whitespace() {
+        if let Some(tag) = word.strip_prefix('#').and_then(parse_tag) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        } else if let Some(p) = parse_priority(word) {
+            priority = Some(p);
+        } else {
+            words.push(word);
+        }
+    }
+    // 何も取り出さなければ改行などもそのまま残す
+    if tags.is_empty() && priority.is_none() {
+        return (text.to_string(), tags, priority);
+    }
+    (words.join(" "), tags, priority)
+}
+
+// 本文中の <@id> と <@!id> のユーザー
+fn mentioned_users(text: &str) -> Vec<u64> {
+    let re = regex::Regex::new(r"<@!?(\d+)>").unwrap();
+    re.captures_iter(text)
+        .filter_map(|captures| captures[1].parse().ok())
+        .collect()
+}
+
+// 新しく担当になったユーザーに DM で知らせる。自分で自分を担当にしたときは送らない
+async fn notify_assignees(ctx: &Context, todo: &Todo, assignees: &[u64], by: &User) {
+    for &id in assignees.iter().filter(|&&id| id != by.id.0) {
+        let channel = match UserId(id).create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel,
+            Err(why) => {
+                error!("Error creating dm channel: {:?}", why);
+                continue;
+            }
+        };
+        let content = format!(
+            "{} さんから TODO の担当になりました。\n{} {}",
+            by.name, todo.id, todo.text
+        );
+        if let Err(why) = channel.say(&ctx.http, content).await {
+            error!("Error sending dm: {:?}", why);
+        }
+    }
+}
+
+// 前に指定したタイムゾーン、なければ JST
+async fn time_zone(ctx: &Context, user_id: UserId) -> TimeZone {
+    let data = ctx.data.read().await;
+    data.get::<TimeZones>()
+        .and_then(|time_zones| time_zones.get(&user_id).copied())
+        .unwrap_or_else(TimeZone::jst)
+}
+
+// 追加して、担当者に知らせる
+async fn add(ctx: &Context, user: &User, todo: Todo, now: i64) -> String {
+    if let Err(message) = todo.check_size() {
+        return message;
+    }
+    let todo = match store::get(ctx).await.add_todo(todo).await {
+        Ok(todo) => todo,
+        Err(_) => return "メッセージの送信に失敗しました".to_string(),
+    };
+    notify_assignees(ctx, &todo, &todo.assignees, user).await;
+    let content = format!("{} を追加しました。", format_todo(&todo, now));
+    push_history(ctx, user.id, Change::Added(todo)).await;
+    content
+}
+
+// todo を edited に置き換えて、新しく担当になったユーザーに知らせる
+async fn edit(ctx: &Context, user: &User, todo: &Todo, edited: Todo, now: i64) -> String {
+    if let Err(message) = edited.check_size() {
+        return message;
+    }
+    if store::get(ctx)
+        .await
+        .update_todo(edited.clone())
+        .await
+        .is_err()
+    {
+        return "メッセージの編集に失敗しました".to_string();
+    }
+    let added = edited
+        .assignees
+        .iter()
+        .filter(|id| !todo.assignees.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
+    notify_assignees(ctx, &edited, &added, user).await;
+    push_history(ctx, user.id, Change::Updated(todo.clone(), edited.clone())).await;
+    format!("{} を編集しました。", format_todo(&edited, now))
+}
+
+async fn set_done(ctx: &Context, user: &User, todo: &Todo, done: bool, now: i64) -> String {
+    if todo.done.is_some() == done {
+        return match done {
+            true => format!("{} は完了済みです。", todo.id),
+            false => format!("{} は完了していません。", todo.id),
+        };
+    }
+    let updated = Todo {
+        done: match done {
+            true => Some(Completion {
+                at: now,
+                by: user.id.0,
+            }),
+            false => None,
+        },
+        ..todo.clone()
+    };
+    if store::get(ctx)
+        .await
+        .update_todo(updated.clone())
+        .await
+        .is_err()
+    {
+        return "メッセージの編集に失敗しました".to_string();
+    }
+    push_history(ctx, user.id, Change::Updated(todo.clone(), updated)).await;
+    match done {
+        true => format!("{}: {} を完了しました。", todo.id, todo.text),
+        false => format!("{}: {} を未完了に戻しました。", todo.id, todo.text),
+    }
+}
+
+async fn remove(ctx: &Context, user: &User, todo: &Todo) -> String {
+    if store::get(ctx).await.remove_todo(todo.id).await.is_err() {
+        return "メッセージの削除に失敗しました".to_string();
+    }
+    push_history(ctx, user.id, Change::Removed(todo.clone())).await;
+    format!("{} を削除しました。", todo.id)
+}
+
+// 添付ファイルの todo をまとめて追加する。担当者への通知は送らない
+async fn import(
+    ctx: &Context,
+    user: &User,
+    channel_id: ChannelId,
+    attachment: Option<&Attachment>,
+    format: Option<Format>,
+    now: i64,
+) -> String {
+    let attachment = match attachment {
+        Some(attachment) => attachment,
+        None => return "読み込むファイルを添付してください".to_string(),
+    };
+    if attachment.size > MAX_IMPORT_BYTES {
+        return format!("ファイルは {} KB までです", MAX_IMPORT_BYTES / 1024);
+    }
+    let format = match format.or_else(|| Format::from_filename(&attachment.filename)) {
+        Some(format) => format,
+        None => return "ファイルの形式が分かりません。format を指定してください".to_string(),
+    };
+    let bytes = match attachment.download().await {
+        Ok(bytes) => bytes,
+        Err(why) => {
+            error!("Error downloading attachment: {:?}", why);
+            return "ファイルの取得に失敗しました".to_string();
+        }
+    };
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return "ファイルは UTF-8 で保存してください".to_string(),
+    };
+    let completion = Completion {
+        at: now,
+        by: user.id.0,
+    };
+    let todos = match transfer::import(&text, format, completion) {
+        Ok(todos) => todos,
+        Err(message) => return message,
+    };
+    if todos.is_empty() {
+        return "読み込める TODO がありません".to_string();
+    }
+    if todos.len() > MAX_IMPORT_TODOS {
+        return format!("一度に読み込めるのは {} 件までです", MAX_IMPORT_TODOS);
+    }
+    for (i, todo) in todos.iter().enumerate() {
+        if let Err(message) = todo.check_size() {
+            return format!("{} 件目: {}", i + 1, message);
+        }
+    }
+
+    let store = store::get(ctx).await;
+    let mut added = Vec::new();
+    let mut failed = false;
+    for todo in todos {
+        // 期限の通知は読み込んだチャンネルに送る
+        let todo = Todo {
+            channel: Some(channel_id.0),
+            ..todo
+        };
+        match store.add_todo(todo).await {
+            Ok(todo) => added.push(todo),
+            Err(_) => {
+                failed = true;
+                break;
+            }
+        }
+    }
+    let count = added.len();
+    // 途中で失敗しても、追加できた分は undo で取り消せるようにする
+    if !added.is_empty() {
+        push_history(ctx, user.id, Change::Imported(added)).await;
+    }
+    match failed {
+        true => format!("{} 件を追加したところで失敗しました", count),
+        false => format!("{} 件の TODO を読み込みました。", count),
+    }
+}
+
+// import で追加した todo をまとめて消す。その後に変更されたものは残す
+async fn undo_import(ctx: &Context, user: &User, todos: Vec<Todo>) -> String {
+    let store = store::get(ctx).await;
+    let mut removed = 0;
+    let mut kept = Vec::new();
+    for (i, todo) in todos.iter().enumerate() {
+        match store.replace_todo(todo.id, Some(todo), None).await {
+            Ok(true) => removed += 1,
+            Ok(false) => kept.push(todo.id.to_string()),
+            Err(_) => {
+                // 残りはもう一度 undo できるよう戻しておく
+                push_history(ctx, user.id, Change::Imported(todos[i..].to_vec())).await;
+                return format!("{} 件を取り消したところで失敗しました", removed);
+            }
+        }
+    }
+    match kept.is_empty() {
+        true => format!("読み込んだ {} 件を取り消しました。", removed),
+        false => format!(
+            "読み込んだ {} 件を取り消しました。{} はその後に変更されているため残しました。",
+            removed,
+            kept.join(", ")
+        ),
+    }
+}
+
+// 自分の最後の変更を取り消す。その後に誰かが同じ todo を変えていれば取り消さない
+async fn undo(ctx: &Context, user: &User) -> String {
+    let change = match pop_history(ctx, user.id).await {
+        Some(change) => change,
+        None => return "取り消せる変更はありません。".to_string(),
+    };
+    let store = store::get(ctx).await;
+    let (id, result, message) = match &change {
+        Change::Added(todo) => (
+            todo.id,
+            store.replace_todo(todo.id, Some(todo), None).await,
+            format!("{} の追加を取り消しました。", todo.id),
+        ),
+        Change::Removed(todo) => (
+            todo.id,
+            store.replace_todo(todo.id, None, Some(todo.clone())).await,
+            format!("{}: {} の削除を取り消しました。", todo.id, todo.text),
+        ),
+        Change::Updated(before, after) => (
+            before.id,
+            store
+                .replace_todo(before.id, Some(after), Some(before.clone()))
+                .await,
+            format!(
+                "{} を {} に戻しました。",
+                before.id,
+                format_todo(before, Utc::now().timestamp())
+            ),
+        ),
+        Change::Imported(todos) => return undo_import(ctx, user, todos.clone()).await,
+    };
+    match result {
+        Ok(true) => message,
+        Ok(false) => format!("{} はその後に変更されているため取り消せません。", id),
+        Err(_) => {
+            // 失敗したらもう一度 undo できるよう戻しておく
+            push_history(ctx, user.id, change).await;
+            "変更の取り消しに失敗しました".to_string()
+        }
+    }
+}
+
+// 時間のかかる操作。先に考え中の返事をしておき、終わったら run の結果で書き換える。
+// import とその undo は todo ごとに db チャンネルへ書き込むので、3 秒以内に返事ができないことがある
+pub fn is_deferred(command: &ApplicationCommandInteraction) -> bool {
+    command.data.options.iter().any(|option| {
+        option.name == "operation"
+            && matches!(&option.resolved, Some(CommandDataOptionValue::String(name)) if name == "import" || name == "undo")
+    })
+}
+
+pub async fn run(command: &ApplicationCommandInteraction, ctx: &Context) -> Response {
+    let options = &command.data.options;
+    let user = &command.user;
+    let now = Utc::now();
+    let operation = match options.iter().find(|option| option.name == "operation") {
+        Some(option) => match &option.resolved {
+            Some(value) => match value {
+                CommandDataOptionValue::String(text) => text,
+                _ => return "オペレーションが不正です".into(),
+            },
+            None => return "オペレーションが不正です".into(),
+        },
+        None => return "オペレーションが不正です".into(),
+    };
+
+    let todo_message = match options.iter().find(|option| option.name == "message") {
+        Some(option) => match &option.resolved {
+            Some(value) => match value {
+                CommandDataOptionValue::String(text) => text,
+                _ => "",
+            },
+            None => "",
+        },
+        None => "",
+    };
+
+    let todo_id = match options.iter().find(|option| option.name == "id") {
+        Some(option) => match &option.resolved {
+            Some(value) => match value {
+                CommandDataOptionValue::String(text) => text,
+                _ => "",
+            },
+            None => "",
+        },
+        None => "",
+    };
+
+    let assignee = match options.iter().find(|option| option.name == "assignee") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.id.0),
+            _ => return "担当者が不正です".into(),
+        },
+        None => None,
+    };
+    // 担当者の指定と本文中のメンション
+    let mut assignees = assignee.into_iter().collect::<Vec<_>>();
+    for id in mentioned_users(todo_message) {
+        if !assignees.contains(&id) {
+            assignees.push(id);
+        }
+    }
+
+    let filter = match options.iter().find(|option| option.name == "filter") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match Filter::from_name(name) {
+                Some(filter) => filter,
+                None => return "フィルターが不正です".into(),
+            },
+            _ => return "フィルターが不正です".into(),
+        },
+        None => Filter::Open,
+    };
+
+    let tag = match options.iter().find(|option| option.name == "tag") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(tag)) => {
+                let tag = tag.trim_start_matches('#');
+                if tag.chars().count() > MAX_TAG_CHARS {
+                    return format!("タグは {} 文字までです", MAX_TAG_CHARS).into();
+                }
+                match parse_tag(tag) {
+                    Some(tag) => Some(tag),
+                    None => return format!("タグ {} は使えません", tag).into(),
+                }
+            }
+            _ => return "タグが不正です".into(),
+        },
+        None => None,
+    };
+
+    let priority = match options.iter().find(|option| option.name == "priority") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match parse_priority(name) {
+                Some(priority) => Some(priority),
+                None => return "優先度が不正です".into(),
+            },
+            _ => return "優先度が不正です".into(),
+        },
+        None => None,
+    };
+
+    let sort = match options.iter().find(|option| option.name == "sort") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match Sort::from_name(name) {
+                Some(sort) => sort,
+                None => return "並べ方が不正です".into(),
+            },
+            _ => return "並べ方が不正です".into(),
+        },
+        None => Sort::Id,
+    };
+
+    // 1 始まり
+    let page = match options.iter().find(|option| option.name == "page") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::Integer(page)) if *page >= 1 => *page as usize,
+            _ => return "ページが不正です".into(),
+        },
+        None => 1,
+    };
+
+    let file_format = match options.iter().find(|option| option.name == "format") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => match Format::from_name(name) {
+                Some(format) => Some(format),
+                None => return "形式が不正です".into(),
+            },
+            _ => return "形式が不正です".into(),
+        },
+        None => None,
+    };
+
+    let attachment = match options.iter().find(|option| option.name == "file") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::Attachment(attachment)) => Some(attachment),
+            _ => return "ファイルが不正です".into(),
+        },
+        None => None,
+    };
+
+    // 指定がなければ前に指定したもの、それもなければ JST
+    let time_zone = match options.iter().find(|option| option.name == "timezone") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(name)) => {
+                match TimeZone::find(&name.to_uppercase()) {
+                    Some(time_zone) => {
+                        let mut data = ctx.data.write().await;
+                        data.entry::<TimeZones>()
+                            .or_insert_with(HashMap::new)
+                            .insert(user.id, time_zone);
+                        time_zone
+                    }
+                    None => return format!("タイムゾーン {} は使えません", name).into(),
+                }
+            }
+            _ => return "タイムゾーンが不正です".into(),
+        },
+        None => time_zone(ctx, user.id).await,
+    };
+
+    // 指定がなければ None、none なら期限をなくす
+    let due = match options.iter().find(|option| option.name == "due") {
+        Some(option) => match &option.resolved {
+            Some(CommandDataOptionValue::String(text)) if text == "none" || text == "なし" => {
+                Some(None)
+            }
+            Some(CommandDataOptionValue::String(text)) => match due::parse(text, now, &time_zone) {
+                Ok(due) => Some(Some(due)),
+                Err(message) => return message.into(),
+            },
+            _ => return "期限が不正です".into(),
+        },
+        None => None,
+    };
+
+    if operation == "undo" {
+        return undo(ctx, user).await.into();
+    }
+    if operation == "import" {
+        let channel_id = command.channel_id;
+        return import(
+            ctx,
+            user,
+            channel_id,
+            attachment,
+            file_format,
+            now.timestamp(),
+        )
+        .await
+        .into();
+    }
+
+    // 本文がなければフォームで入力してもらう
+    if operation == "add" && todo_message.is_empty() {
+        return component::form(None, None, &time_zone);
+    }
+
+    let todos = match store::get(ctx).await.todos().await {
+        Ok(todos) => todos,
+        Err(_) => return "メッセージの取得に失敗しました".into(),
+    };
+
+    // id か本文が一致する todo
+    let find = |key: &str| {
+        todos
+            .iter()
+            .find(|todo| !key.is_empty() && (todo.id.to_string() == key || todo.text == key))
+    };
+
+    match operation.as_str() {
+        "add" => {
+            let (text, tags, parsed) = parse_labels(todo_message);
+            if text.is_empty() {
+                return "本文がありません".into();
+            }
+            let todo = Todo {
+                creator: Some(user.id.0),
+                assignees,
+                due: due.flatten(),
+                channel: Some(command.channel_id.0),
+                tags,
+                priority: priority.or(parsed),
+                ..Todo::new(0, text)
+            };
+            return add(ctx, user, todo, now.timestamp()).await.into();
+        }
+        "rm" => match find(todo_message).or_else(|| find(todo_id)) {
+            Some(todo) => return remove(ctx, user, todo).await.into(),
+            None => {
+                return format!("{} は見つかりませんでした。", todo_message).into();
+            }
+        },
+        "ls" => {
+            let view = View {
+                index: page - 1,
+                query: Query {
+                    filter,
+                    tag,
+                    priority,
+                    assignee,
+                    due_before: due.flatten(),
+                    sort,
+                },
+            };
+            return component::list(&todos, &view, None, user.id.0, now.timestamp()).private();
+        }
+
+        // 完了したものも含めてすべて書き出す
+        "export" => {
+            let format = file_format.unwrap_or(Format::Markdown);
+            let file = AttachmentType::Bytes {
+                data: transfer::export(&todos, format).into_bytes().into(),
+                filename: format!("todo.{}", format.extension()),
+            };
+            return Response {
+                files: vec![file],
+                ..format!("{} 件の TODO を書き出しました。", todos.len()).into()
+            };
+        }
+
+        "edit" => match find(todo_id).or_else(|| find(todo_message)) {
+            // 何も指定がなければフォームで編集してもらう
+            Some(todo)
+                if todo_message.is_empty()
+                    && assignees.is_empty()
+                    && priority.is_none()
+                    && due.is_none() =>
+            {
+                return component::form(Some(todo), None, &time_zone);
+            }
+            Some(todo) => {
+                // 担当者やタグだけ変えるときは本文を変えない
+                let (text, tags, parsed) = parse_labels(todo_message);
+                let text = match text.is_empty() {
+                    true => todo.text.clone(),
+                    false => text,
+                };
+                // 本文にタグがあれば置き換える
+                let tags = match tags.is_empty() {
+                    true => todo.tags.clone(),
+                    false => tags,
+                };
+                let mut edited = Todo {
+                    text,
+                    tags,
+                    priority: priority.or(parsed).or(todo.priority),
+                    assignees: todo.assignees.clone(),
+                    ..todo.clone()
+                };
+                for id in assignees {
+                    if !edited.assignees.contains(&id) {
+                        edited.assignees.push(id);
+                    }
+                }
+                if let Some(due) = due {
+                    // 期限が変わったら改めて通知する
+                    edited.reminded = edited.reminded && edited.due == due;
+                    edited.due = due;
+                }
+                return edit(ctx, user, todo, edited, now.timestamp()).await.into();
+            }
+            None => return format!("{} は存在しません。", todo_id).into(),
+        },
+        "done" | "reopen" => match find(todo_id).or_else(|| find(todo_message)) {
+            Some(todo) => {
+                let done = operation == "done";
+                return set_done(ctx, user, todo, done, now.timestamp())
+                    .await
+                    .into();
+            }
+            None => {
+                let key = if todo_id.is_empty() {
+                    todo_message
+                } else {
+                    todo_id
+                };
+                return format!("{} は存在しません。", key).into();
+            }
+        },
+        _ => {}
+    }
+
+    "".into()
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("todo")
+        .description("todo list を管理します")
+        .create_option(|option| {
+            option
+                .name("operation")
+                .kind(CommandOptionType::String)
+                .description("オペレーション")
+                .add_string_choice("add", "add")
+                .add_string_choice("rm", "rm")
+                .add_string_choice("ls", "ls")
+                .add_string_choice("edit", "edit")
+                .add_string_choice("done", "done")
+                .add_string_choice("reopen", "reopen")
+                .add_string_choice("undo", "undo")
+                .add_string_choice("export", "export")
+                .add_string_choice("import", "import")
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("message")
+                .kind(CommandOptionType::String)
+                .description("メッセージ (add で省略するとフォームを開く)")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("id")
+                .kind(CommandOptionType::String)
+                .description("ID")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("filter")
+                .kind(CommandOptionType::String)
+                .description("ls で表示する TODO (省略すると open)")
+                .add_string_choice("open", "open")
+                .add_string_choice("done", "done")
+                .add_string_choice("all", "all")
+                .add_string_choice("mine", "mine")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("assignee")
+                .kind(CommandOptionType::User)
+                .description("担当者。ls ではこのユーザーが担当する TODO だけ表示する")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("due")
+                .kind(CommandOptionType::String)
+                .description("期限 (tomorrow 10:00, in 3d, friday, 2026-10-20 18:00, none)。ls ではこれまでに期限がくるもの")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("timezone")
+                .kind(CommandOptionType::String)
+                .description("期限のタイムゾーン (JST, UTC, PST など。省略すると前回の指定か JST)")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("tag")
+                .kind(CommandOptionType::String)
+                .description("ls で表示するタグ")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("priority")
+                .kind(CommandOptionType::String)
+                .description("優先度。ls ではこの優先度だけ表示する")
+                .add_string_choice("p1", "p1")
+                .add_string_choice("p2", "p2")
+                .add_string_choice("p3", "p3")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("sort")
+                .kind(CommandOptionType::String)
+                .description("ls の並べ方 (省略すると id)")
+                .add_string_choice("id", "id")
+                .add_string_choice("priority", "priority")
+                .add_string_choice("due", "due")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("format")
+                .kind(CommandOptionType::String)
+                .description("export と import の形式 (export で省略すると markdown、import ではファイル名から判断する)")
+                .add_string_choice("markdown", "markdown")
+                .add_string_choice("json", "json")
+                .add_string_choice("csv", "csv")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("file")
+                .kind(CommandOptionType::Attachment)
+                .description("import で読み込むファイル")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("page")
+                .kind(CommandOptionType::Integer)
+                .description("ls で表示するページ")
+                .min_int_value(1)
+                .required(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        assert_eq!(
+            parse_labels("fix #Bug login p1 #ui #bug"),
+            (
+                "fix login".to_string(),
+                vec!["bug".to_string(), "ui".to_string()],
+                Some(1)
+            )
+        );
+        assert_eq!(
+            parse_labels("<#10> を見る #日本語_tag P3"),
+            (
+                "<#10> を見る".to_string(),
+                vec!["日本語_tag".to_string()],
+                Some(3)
+            )
+        );
+        // p4 や # だけの単語は本文のまま
+        assert_eq!(
+            parse_labels("p4 #  issue#1"),
+            ("p4 #  issue#1".to_string(), vec![], None)
+        );
+        // 長すぎるタグも本文のまま
+        let long = format!("#{}", "a".repeat(MAX_TAG_CHARS + 1));
+        assert_eq!(parse_labels(&long), (long.clone(), vec![], None));
+        assert_eq!(
+            parse_labels("#bug p2"),
+            (String::new(), vec!["bug".to_string()], Some(2))
+        );
+    }
+
+    #[test]
+    fn test_mentioned_users() {
+        assert_eq!(
+            mentioned_users("review <@42> と <@!7>、<@abc> <#10>"),
+            vec![42, 7]
+        );
+        assert_eq!(mentioned_users("牛乳を買う"), Vec::<u64>::new());
+    }
+}
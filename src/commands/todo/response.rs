@@ -0,0 +1,98 @@
+use serenity::builder::{
+    CreateComponents, CreateEmbed, CreateInteractionResponse, EditInteractionResponse,
+};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::AttachmentType;
+
+// /todo の返事。ls は embed とボタンで 1 ページずつ表示し、add や edit はフォームを開くことがある
+pub struct Response {
+    pub kind: InteractionResponseType,
+    pub content: String,
+    pub embed: Option<CreateEmbed>,
+    pub components: CreateComponents,
+    // export で書き出したファイル
+    pub files: Vec<AttachmentType<'static>>,
+    // フォームの custom_id と題名
+    pub modal: Option<(String, String)>,
+    // 実行した本人にだけ見せる
+    pub ephemeral: bool,
+}
+
+impl Response {
+    // ボタンを押したメッセージを書き換える
+    pub fn update(self) -> Self {
+        Response {
+            kind: InteractionResponseType::UpdateMessage,
+            ..self
+        }
+    }
+
+    // ボタンは押した人の条件で一覧を書き換えるので、一覧は本人にだけ見せる
+    pub fn private(self) -> Self {
+        Response {
+            ephemeral: true,
+            ..self
+        }
+    }
+
+    pub fn build<'a, 'b>(
+        self,
+        response: &'b mut CreateInteractionResponse<'a>,
+    ) -> &'b mut CreateInteractionResponse<'a> {
+        response.kind(self.kind).interaction_response_data(|data| {
+            // 書き換えるときに前の embed やボタンが残らないよう、空でも設定する
+            data.set_components(self.components).add_files(self.files);
+            if self.ephemeral {
+                data.ephemeral(true);
+            }
+            match self.modal {
+                Some((custom_id, title)) => data.custom_id(custom_id).title(title),
+                // 担当者などはメンションで表示するだけで、通知は個別に送る
+                None => data
+                    .content(self.content)
+                    .set_embeds(self.embed)
+                    .allowed_mentions(|mentions| mentions.empty_parse()),
+            }
+        })
+    }
+}
+
+impl Response {
+    // 考え中の返事を書き換える。ファイルとフォームは送れない
+    pub fn edit(self, response: &mut EditInteractionResponse) -> &mut EditInteractionResponse {
+        let Response {
+            content,
+            embed,
+            components,
+            ..
+        } = self;
+        response
+            .content(content)
+            .set_embeds(embed.into_iter().collect())
+            .components(|c| {
+                *c = components;
+                c
+            })
+            .allowed_mentions(|mentions| mentions.empty_parse())
+    }
+}
+
+impl From<String> for Response {
+    fn from(content: String) -> Self {
+        Response {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            content,
+            embed: None,
+            components: CreateComponents::default(),
+            files: Vec::new(),
+            modal: None,
+            ephemeral: false,
+        }
+    }
+}
+
+impl From<&str> for Response {
+    fn from(content: &str) -> Self {
+        content.to_string().into()
+    }
+}
@@ -0,0 +1,557 @@
+use chrono::{DateTime, SecondsFormat};
+
+use crate::store::{Completion, Todo};
+
+use super::{mentioned_users, parse_labels, parse_priority, parse_tag};
+
+const CSV_HEADER: [&str; 10] = [
+    "id",
+    "text",
+    "status",
+    "due",
+    "priority",
+    "tags",
+    "assignees",
+    "creator",
+    "done_at",
+    "done_by",
+];
+
+// 書き出しと読み込みの形式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Markdown,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" | "md" => Some(Format::Markdown),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    // 添付ファイルの拡張子から
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        Format::from_name(&extension.to_lowercase())
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+// 他のツールでも読めるよう、日時は UTC の RFC 3339 にする
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|datetime| datetime.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+fn parse_time(text: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(text.trim())
+        .map(|datetime| datetime.timestamp())
+        .map_err(|_| format!("日時 {} を読めません", text))
+}
+
+// 本文に書かれていない担当者をメンションで添える
+fn with_assignees(todo: &Todo) -> String {
+    let mentioned = mentioned_users(&todo.text);
+    let mut text = todo.text.clone();
+    for id in todo.assignees.iter().filter(|id| !mentioned.contains(id)) {
+        text.push_str(&format!(" <@{}>", id));
+    }
+    text
+}
+
+// `- [x] review #bug p1 <@42> (due: 2026-10-20T09:00:00Z)`
+fn export_markdown(todos: &[Todo]) -> String {
+    let mut lines = vec!["# TODO".to_string(), String::new()];
+    for todo in todos {
+        let mut line = format!(
+            "- [{}] {}",
+            if todo.done.is_some() { "x" } else { " " },
+            with_assignees(todo)
+        );
+        for tag in &todo.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some(priority) = todo.priority {
+            line.push_str(&format!(" p{}", priority));
+        }
+        if let Some(due) = todo.due {
+            line.push_str(&format!(" (due: {})", format_time(due)));
+        }
+        lines.push(line);
+    }
+    lines.join("\n") + "\n"
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn export_csv(todos: &[Todo]) -> String {
+    let join = |ids: &[u64]| {
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut rows = vec![CSV_HEADER.join(",")];
+    for todo in todos {
+        let fields = [
+            todo.id.to_string(),
+            todo.text.clone(),
+            if todo.done.is_some() { "done" } else { "open" }.to_string(),
+            todo.due.map(format_time).unwrap_or_default(),
+            todo.priority
+                .map(|priority| format!("p{}", priority))
+                .unwrap_or_default(),
+            todo.tags.join(" "),
+            join(&todo.assignees),
+            todo.creator.map(|id| id.to_string()).unwrap_or_default(),
+            todo.done
+                .as_ref()
+                .map(|done| format_time(done.at))
+                .unwrap_or_default(),
+            todo.done
+                .as_ref()
+                .map(|done| done.by.to_string())
+                .unwrap_or_default(),
+        ];
+        rows.push(
+            fields
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    rows.join("\r\n") + "\r\n"
+}
+
+/// todos を id 順に format の文字列にする
+pub fn export(todos: &[Todo], format: Format) -> String {
+    let mut todos = todos.to_vec();
+    todos.sort_by_key(|todo| todo.id);
+    match format {
+        Format::Markdown => export_markdown(&todos),
+        Format::Json => serde_json::to_string_pretty(&todos).unwrap_or_default(),
+        Format::Csv => export_csv(&todos),
+    }
+}
+
+// 読み込んだ本文から #tag、p1〜p3、担当者を取り出す
+fn parse_text(text: &str) -> Result<Todo, String> {
+    let (body, tags, priority) = parse_labels(text);
+    if body.trim().is_empty() {
+        return Err("本文がありません".to_string());
+    }
+    let mut assignees = Vec::new();
+    for id in mentioned_users(text) {
+        if !assignees.contains(&id) {
+            assignees.push(id);
+        }
+    }
+    Ok(Todo {
+        tags,
+        priority,
+        assignees,
+        ..Todo::new(0, body)
+    })
+}
+
+fn import_markdown(text: &str, completion: Completion) -> Result<Vec<Todo>, String> {
+    let item = regex::Regex::new(r"^[-*] \[([ xX])\] (.*)$").unwrap();
+    let due = regex::Regex::new(r"\s*\(due: ([^)]*)\)\s*$").unwrap();
+    let mut todos = Vec::new();
+    // チェックボックスのない行は見出しやメモとして読み飛ばす
+    for (number, line) in text.lines().enumerate() {
+        let captures = match item.captures(line.trim()) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let error = |message: String| format!("{} 行目: {}", number + 1, message);
+        let line = &captures[2];
+        // 末尾の (due: ...) は期限にして、本文からは除く
+        let (body, todo_due) = match due.captures(line) {
+            Some(due) => (
+                &line[..line.len() - due[0].len()],
+                Some(parse_time(&due[1]).map_err(error)?),
+            ),
+            None => (line, None),
+        };
+        let todo = Todo {
+            due: todo_due,
+            done: match &captures[1] {
+                " " => None,
+                _ => Some(completion),
+            },
+            ..parse_text(body).map_err(error)?
+        };
+        todos.push(todo);
+    }
+    Ok(todos)
+}
+
+// RFC 4180 の CSV。"" で囲んだ欄には , や改行を含められる
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("\" が閉じられていません".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // 空行は読み飛ばす
+    rows.retain(|row| row.iter().any(|field| !field.is_empty()));
+    Ok(rows)
+}
+
+fn parse_ids(text: &str) -> Result<Vec<u64>, String> {
+    text.split_whitespace()
+        .map(|id| {
+            id.trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>')
+                .parse()
+                .map_err(|_| format!("ユーザー {} を読めません", id))
+        })
+        .collect()
+}
+
+fn import_csv(text: &str, completion: Completion) -> Result<Vec<Todo>, String> {
+    let rows = parse_csv(text)?;
+    let (header, rows) = match rows.split_first() {
+        Some(split) => split,
+        None => return Ok(Vec::new()),
+    };
+    // 欄の順番は問わない。text 以外の欄はなくてもよい
+    let column = |name: &str| header.iter().position(|field| field.trim() == name);
+    let text_column = column("text").ok_or("text の欄がありません")?;
+    let columns = CSV_HEADER.map(column);
+    let mut todos = Vec::new();
+    for (number, row) in rows.iter().enumerate() {
+        // ヘッダーが 1 行目なので、データは 2 行目から
+        let error = |message: String| format!("{} 行目: {}", number + 2, message);
+        let get = |index: usize| {
+            columns[index]
+                .and_then(|column| row.get(column))
+                .map(|field| field.trim())
+                .filter(|field| !field.is_empty())
+        };
+        let text = row.get(text_column).map(String::as_str).unwrap_or("");
+        let mut todo = parse_text(text).map_err(error)?;
+        if let Some(due) = get(3) {
+            todo.due = Some(parse_time(due).map_err(error)?);
+        }
+        if let Some(priority) = get(4) {
+            todo.priority = Some(
+                parse_priority(priority)
+                    .ok_or_else(|| error(format!("優先度 {} を読めません", priority)))?,
+            );
+        }
+        if let Some(tags) = get(5) {
+            for tag in tags.split_whitespace() {
+                let tag = parse_tag(tag.trim_start_matches('#'))
+                    .ok_or_else(|| error(format!("タグ {} を読めません", tag)))?;
+                if !todo.tags.contains(&tag) {
+                    todo.tags.push(tag);
+                }
+            }
+        }
+        if let Some(assignees) = get(6) {
+            for id in parse_ids(assignees).map_err(error)? {
+                if !todo.assignees.contains(&id) {
+                    todo.assignees.push(id);
+                }
+            }
+        }
+        if let Some(creator) = get(7) {
+            todo.creator = parse_ids(creator).map_err(error)?.first().copied();
+        }
+        let done = match get(2) {
+            None | Some("open") => false,
+            Some("done") => true,
+            Some(status) => return Err(error(format!("状態 {} を読めません", status))),
+        };
+        if done {
+            todo.done = Some(Completion {
+                at: match get(8) {
+                    Some(at) => parse_time(at).map_err(error)?,
+                    None => completion.at,
+                },
+                by: match get(9) {
+                    Some(by) => parse_ids(by)
+                        .map_err(error)?
+                        .first()
+                        .copied()
+                        .unwrap_or(completion.by),
+                    None => completion.by,
+                },
+            });
+        }
+        todos.push(todo);
+    }
+    Ok(todos)
+}
+
+// JSON は書き出したものを手で直すこともあるので、本文から読むときと同じ決まりで確かめる
+fn check_json(todo: Todo) -> Result<Todo, String> {
+    if todo.text.trim().is_empty() {
+        return Err("本文がありません".to_string());
+    }
+    if let Some(priority) = todo.priority.filter(|priority| !(1..=3).contains(priority)) {
+        return Err(format!("優先度 {} は使えません", priority));
+    }
+    let mut tags = Vec::new();
+    for tag in &todo.tags {
+        let tag = parse_tag(tag.trim_start_matches('#'))
+            .ok_or_else(|| format!("タグ {} を読めません", tag))?;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(Todo { tags, ..todo })
+}
+
+fn import_json(text: &str) -> Result<Vec<Todo>, String> {
+    let todos = serde_json::from_str::<Vec<Todo>>(text)
+        .map_err(|why| format!("JSON を読めません: {}", why))?;
+    todos
+        .into_iter()
+        .enumerate()
+        .map(|(i, todo)| check_json(todo).map_err(|message| format!("{} 件目: {}", i + 1, message)))
+        .collect()
+}
+
+/// format の文字列を todo にする。id は追加するときに振り直す。
+/// 完了日時や完了した人、作った人が分からなければ completion の日時とユーザーにする
+pub fn import(text: &str, format: Format, completion: Completion) -> Result<Vec<Todo>, String> {
+    // Excel などが付ける BOM
+    let text = text.trim_start_matches('\u{feff}');
+    let todos = match format {
+        Format::Markdown => import_markdown(text, completion)?,
+        Format::Json => import_json(text)?,
+        Format::Csv => import_csv(text, completion)?,
+    };
+    Ok(todos
+        .into_iter()
+        .map(|todo| Todo {
+            creator: todo.creator.or(Some(completion.by)),
+            // 通知先のチャンネルと通知済みの印は読み込んだ側で決め直す
+            channel: None,
+            reminded: false,
+            ..todo
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_792_400_000;
+
+    fn completion() -> Completion {
+        Completion { at: NOW, by: 7 }
+    }
+
+    fn todos() -> Vec<Todo> {
+        vec![
+            Todo {
+                creator: Some(7),
+                assignees: vec![42],
+                tags: vec!["bug".to_string()],
+                priority: Some(1),
+                due: Some(NOW + 3600),
+                ..Todo::new(2, "fix, \"login\"".to_string())
+            },
+            Todo {
+                creator: Some(42),
+                done: Some(Completion {
+                    at: NOW - 60,
+                    by: 42,
+                }),
+                ..Todo::new(1, "牛乳を買う".to_string())
+            },
+        ]
+    }
+
+    #[test]
+    fn test_export() {
+        assert_eq!(
+            export(&todos(), Format::Markdown),
+            "# TODO\n\n- [x] 牛乳を買う\n- [ ] fix, \"login\" <@42> #bug p1 (due: 2026-10-19T09:53:20Z)\n"
+        );
+        assert_eq!(
+            export(&todos(), Format::Csv),
+            "id,text,status,due,priority,tags,assignees,creator,done_at,done_by\r\n\
+             1,牛乳を買う,done,,,,,42,2026-10-19T08:52:20Z,42\r\n\
+             2,\"fix, \"\"login\"\"\",open,2026-10-19T09:53:20Z,p1,bug,42,7,,\r\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut expected = todos();
+        expected.sort_by_key(|todo| todo.id);
+        // id は追加するときに振り直すので比べない
+        let without_ids = |todos: Vec<Todo>| {
+            todos
+                .into_iter()
+                .map(|todo| Todo { id: 0, ..todo })
+                .collect::<Vec<_>>()
+        };
+        for format in [Format::Json, Format::Csv] {
+            let imported = import(&export(&todos(), format), format, completion()).unwrap();
+            assert_eq!(without_ids(imported), without_ids(expected.clone()));
+        }
+
+        // Markdown は完了日時と作った人を残さない
+        let imported = import(
+            &export(&todos(), Format::Markdown),
+            Format::Markdown,
+            completion(),
+        )
+        .unwrap();
+        assert_eq!(
+            imported,
+            vec![
+                Todo {
+                    creator: Some(7),
+                    done: Some(completion()),
+                    ..Todo::new(0, "牛乳を買う".to_string())
+                },
+                Todo {
+                    id: 0,
+                    text: "fix, \"login\" <@42>".to_string(),
+                    ..expected[1].clone()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import() {
+        let markdown = "## Sprint 12\n\nメモ\n* [X] deploy #ops\n- [ ] review p2\n- not a todo\n";
+        let todos = import(markdown, Format::Markdown, completion()).unwrap();
+        assert_eq!(
+            todos
+                .iter()
+                .map(|todo| todo.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["deploy", "review"]
+        );
+        assert_eq!(todos[0].tags, vec!["ops"]);
+        assert_eq!(todos[0].done, Some(completion()));
+        assert_eq!(todos[1].priority, Some(2));
+
+        // 欄の順番や足りない欄、改行を含む欄
+        let csv = "\u{feff}status,text,tags\ndone,\"line 1\nline 2\",#a b\n\n,plain,\n";
+        let todos = import(csv, Format::Csv, completion()).unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].text, "line 1\nline 2");
+        assert_eq!(todos[0].tags, vec!["a", "b"]);
+        assert_eq!(todos[0].done, Some(completion()));
+        assert_eq!(todos[1].done, None);
+        assert_eq!(todos[1].creator, Some(7));
+    }
+
+    #[test]
+    fn test_import_json() {
+        // 通知先と通知済みの印は引き継がず、タグは本文から読むときと同じ形にそろえる
+        let json = r##"[{"id": 5, "text": "deploy", "channel": 99, "reminded": true, "due": 1792400000, "tags": ["#Ops", "ops"]}]"##;
+        let todos = import(json, Format::Json, completion()).unwrap();
+        assert_eq!(
+            todos,
+            vec![Todo {
+                creator: Some(7),
+                due: Some(NOW),
+                tags: vec!["ops".to_string()],
+                ..Todo::new(5, "deploy".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_import_error() {
+        let error = |text: &str, format: Format| import(text, format, completion()).unwrap_err();
+        assert_eq!(
+            error("- [ ] #bug", Format::Markdown),
+            "1 行目: 本文がありません"
+        );
+        assert_eq!(
+            error("- [ ] review (due: tomorrow)", Format::Markdown),
+            "1 行目: 日時 tomorrow を読めません"
+        );
+        assert_eq!(error("title\nreview", Format::Csv), "text の欄がありません");
+        assert_eq!(
+            error("text,status\nreview,closed", Format::Csv),
+            "2 行目: 状態 closed を読めません"
+        );
+        assert_eq!(
+            error("text,priority\nreview,high", Format::Csv),
+            "2 行目: 優先度 high を読めません"
+        );
+        assert_eq!(
+            error("text\n\"review", Format::Csv),
+            "\" が閉じられていません"
+        );
+        assert!(error("{}", Format::Json).starts_with("JSON を読めません"));
+        assert_eq!(
+            error(
+                r#"[{"id": 1, "text": "a"}, {"id": 2, "text": "b", "priority": 9}]"#,
+                Format::Json
+            ),
+            "2 件目: 優先度 9 は使えません"
+        );
+        assert_eq!(
+            error(r#"[{"id": 1, "text": "a", "tags": ["a b"]}]"#, Format::Json),
+            "1 件目: タグ a b を読めません"
+        );
+        assert_eq!(
+            error("text,tags\nreview,#a/b", Format::Csv),
+            "2 行目: タグ #a/b を読めません"
+        );
+        assert_eq!(Format::from_filename("Sprint.MD"), Some(Format::Markdown));
+        assert_eq!(Format::from_filename("todo.txt"), None);
+    }
+}
@@ -1,39 +1,107 @@
 use serenity::{
     client::Context,
-    model::application::interaction::{Interaction, InteractionResponseType},
+    model::application::interaction::{
+        application_command::ApplicationCommandInteraction, Interaction, InteractionResponseType,
+    },
 };
 use tracing::{error, info};
 
 use crate::commands;
 
 pub async fn interaction_create(ctx: Context, interaction: Interaction) {
-    if let Interaction::ApplicationCommand(command) = interaction {
-        info!("called command: {:?}", command.data.name);
-        let content = match command.data.name.as_str() {
-            "random" => commands::random::run(&command.data.options),
-            "friday" => commands::friday::run(&command.data.options),
-            "cat" => commands::cat::run(&command.data.options),
-            "wiki" => commands::wiki::run(&command.data.options).await,
-            "eval" => commands::eval::run(&command.data.options).await,
-            "todo" => commands::todo::run(&command.data.options, &ctx).await,
-            "image" => commands::image::run(&command.data.options).await,
-            "github_trend" => commands::github_trend::run(&command, &ctx).await,
-            "mdn" => commands::mdn::run(&command.data.options).await,
-            "levenshtein" => commands::levenshtein::run(&command.data.options),
-            "line" => commands::line::run(&command.data.options),
-            "rss" => commands::rss::run(&command.data.options, &ctx).await,
-            _ => "not implemented :(".to_string(),
-        };
+    match interaction {
+        Interaction::ApplicationCommand(command) => application_command(ctx, command).await,
+        Interaction::MessageComponent(component) => {
+            info!("called component: {:?}", component.data.custom_id);
+            // ボタンや select menu は今のところ /todo の一覧だけ
+            if !component.data.custom_id.starts_with("todo:") {
+                return;
+            }
+            let response = commands::todo::component(&component, &ctx).await;
+            if let Err(why) = component
+                .create_interaction_response(&ctx.http, |r| response.build(r))
+                .await
+            {
+                error!("failed to create interaction response: {:?}", why);
+            }
+        }
+        Interaction::ModalSubmit(modal) => {
+            info!("called modal: {:?}", modal.data.custom_id);
+            if !modal.data.custom_id.starts_with("todo:") {
+                return;
+            }
+            let response = commands::todo::modal_submit(&modal, &ctx).await;
+            if let Err(why) = modal
+                .create_interaction_response(&ctx.http, |r| response.build(r))
+                .await
+            {
+                error!("failed to create interaction response: {:?}", why);
+            }
+        }
+        _ => {}
+    }
+}
 
+async fn application_command(ctx: Context, command: ApplicationCommandInteraction) {
+    info!("called command: {:?}", command.data.name);
+    // /todo は embed やボタン、フォームで返事をする
+    if command.data.name == "todo" {
+        if commands::todo::is_deferred(&command) {
+            if let Err(why) = command
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                })
+                .await
+            {
+                error!("failed to create interaction response: {:?}", why);
+                return;
+            }
+            let response = commands::todo::run(&command, &ctx).await;
+            if let Err(why) = command
+                .edit_original_interaction_response(&ctx.http, |r| response.edit(r))
+                .await
+            {
+                error!("failed to edit interaction response: {:?}", why);
+            }
+            return;
+        }
+        let response = commands::todo::run(&command, &ctx).await;
         if let Err(why) = command
-            .create_interaction_response(&ctx.http, |response| {
-                response
-                    .kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|message| message.content(content))
-            })
+            .create_interaction_response(&ctx.http, |r| response.build(r))
             .await
         {
             error!("failed to create interaction response: {:?}", why);
         }
+        return;
+    }
+
+    let content = match command.data.name.as_str() {
+        "random" => commands::random::run(&command.data.options),
+        "friday" => commands::friday::run(&command.data.options),
+        "cat" => commands::cat::run(&command.data.options),
+        "wiki" => commands::wiki::run(&command.data.options).await,
+        "eval" => commands::eval::run(&command.data.options, &ctx, &command.user).await,
+        "image" => commands::image::run(&command.data.options).await,
+        "github_trend" => commands::github_trend::run(&command, &ctx).await,
+        "mdn" => commands::mdn::run(&command.data.options).await,
+        "levenshtein" => commands::levenshtein::run(&command.data.options),
+        "line" => commands::line::run(&command.data.options),
+        "rss" => commands::rss::run(&command.data.options, &ctx).await,
+        _ => "not implemented :(".to_string(),
+    };
+
+    if let Err(why) = command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .content(content)
+                        .allowed_mentions(|mentions| mentions.empty_parse())
+                })
+        })
+        .await
+    {
+        error!("failed to create interaction response: {:?}", why);
     }
 }
@@ -4,6 +4,7 @@ use serenity::prelude::*;
 use serenity::utils::colours;
 use tracing::error;
 
+use crate::utils::channel_cache;
 use crate::utils::fetch_chatgpt::fetch_chatgpt;
 
 pub async fn message(ctx: Context, msg: Message) {
@@ -87,12 +88,13 @@ pub async fn message(ctx: Context, msg: Message) {
                     None => message.author.default_avatar_url(),
                 };
 
-                let channel_name = match guild.channels(&ctx.http).await {
-                    Ok(channels) => match channels.get(&ChannelId(channel_id)) {
-                        Some(channel) => channel.name.clone(),
-                        None => return,
-                    },
-                    Err(_) => return,
+                let channel_name = match channel_cache::get(&ctx)
+                    .await
+                    .channel(guild, ChannelId(channel_id))
+                    .await
+                {
+                    Ok(Some(channel)) => channel.name,
+                    _ => return,
                 };
 
                 if let Err(why) = msg
@@ -1,9 +1,15 @@
 use serenity::async_trait;
 use serenity::model::application::interaction::Interaction;
-use serenity::model::channel::Message;
+use serenity::model::channel::{Channel, GuildChannel, Message};
+use serenity::model::event::MessageUpdateEvent;
 use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, UnavailableGuild};
+use serenity::model::id::{ChannelId, GuildId, MessageId};
 use serenity::prelude::*;
 
+use crate::store;
+use crate::utils::channel_cache;
+
 mod interaction_create_handler;
 mod message_handler;
 mod ready_handler;
@@ -13,13 +19,68 @@ pub struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, ctx: Context, message: Message) {
+        store::get(&ctx).await.message_created(&message).await;
         message_handler::message(ctx, message).await;
     }
 
+    async fn message_update(&self, ctx: Context, event: MessageUpdateEvent) {
+        store::get(&ctx).await.message_updated(&event).await;
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        _: Option<GuildId>,
+    ) {
+        store::get(&ctx)
+            .await
+            .messages_deleted(channel_id, &[message_id])
+            .await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        message_ids: Vec<MessageId>,
+        _: Option<GuildId>,
+    ) {
+        store::get(&ctx)
+            .await
+            .messages_deleted(channel_id, &message_ids)
+            .await;
+    }
+
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         interaction_create_handler::interaction_create(ctx, interaction).await;
     }
 
+    async fn guild_create(&self, ctx: Context, guild: Guild) {
+        channel_cache::get(&ctx).await.set_guild(&guild);
+    }
+
+    async fn guild_delete(&self, ctx: Context, guild: UnavailableGuild) {
+        channel_cache::get(&ctx).await.remove_guild(guild.id);
+    }
+
+    async fn channel_create(&self, ctx: Context, channel: &GuildChannel) {
+        channel_cache::get(&ctx).await.insert(channel);
+    }
+
+    async fn channel_update(&self, ctx: Context, channel: Channel) {
+        if let Channel::Guild(channel) = channel {
+            channel_cache::get(&ctx).await.insert(&channel);
+        }
+    }
+
+    async fn channel_delete(&self, ctx: Context, channel: &GuildChannel) {
+        channel_cache::get(&ctx)
+            .await
+            .remove(channel.guild_id, channel.id);
+    }
+
     async fn ready(&self, ctx: Context, _: Ready) {
         ready_handler::ready(ctx).await;
     }
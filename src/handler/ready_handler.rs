@@ -1,9 +1,9 @@
 use std::env;
 
-use crate::{commands, scheduler::processer::Processer};
+use crate::{commands, scheduler};
 
 use serenity::{client::Context, model::id::GuildId};
-use tracing::{error, info};
+use tracing::info;
 
 pub async fn ready(ctx: Context) {
     let guild_id = GuildId(889012300705591307);
@@ -26,23 +26,11 @@ pub async fn ready(ctx: Context) {
 
     let mode = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
 
-    // 30分に1回、プロダクションのときのみ実行
+    // todo の通知は 1 分ごと、RSS feed と部分ツイートは 30 分に 1 回、プロダクションのときのみ実行
     if mode == "production" {
-        // RSS feed を取得する
-        let rss_processor = crate::scheduler::rss::ProcesserStruct;
-        match rss_processor.run(&ctx).await {
-            Ok(_) => info!("RSS feed fetched successfully."),
-            Err(why) => error!("Error fetching RSS feed: {:?}", why),
-        }
-
-        // 部分ツイートを取得する
-        let atproto_processor = crate::scheduler::atproto::ProcesserStruct;
-        match atproto_processor.run(&ctx).await {
-            Ok(_) => info!("部分ツイート fetched successfully."),
-            Err(why) => error!("Error fetching 部分ツイート: {:?}", why),
-        }
+        scheduler::spawn(ctx.clone());
     } else {
-        info!("RSS and 部分ツイート are not performed in development mode.");
+        info!("Scheduled jobs are not performed in development mode.");
     }
 
     info!("bot is ready!")
@@ -11,7 +11,6 @@ pub struct HttpRequest {
     pub host: String,
     pub port: u16,
     pub path: String,
-    pub query: HashMap<String, String>,
     pub headers: HashMap<String, String>,
     request: String,
 }
@@ -23,14 +22,12 @@ impl HttpRequest {
         let port = url.port();
         let path = url.path();
         let headers = headers.into_iter().collect();
-        let query = url.query_pairs();
 
         Self {
             url,
             host,
             port,
             path,
-            query,
             headers,
             request: String::new(),
         }
@@ -54,14 +51,14 @@ impl HttpRequest {
     }
 
     pub async fn get(&mut self) -> &mut HttpRequest {
+        // 署名付き URL などは並び順や encode が変わると壊れるので、query は受け取ったまま送る
+        let target = match self.url.query() {
+            Some(query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        };
         let request = format!(
-            "GET {}?{} HTTP/1.1\r\nHost: {}\r\n{}\r\n\r\n",
-            self.path,
-            self.query
-                .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
-                .collect::<Vec<String>>()
-                .join("&"),
+            "GET {} HTTP/1.1\r\nHost: {}\r\n{}\r\n\r\n",
+            target,
             self.host,
             self.headers
                 .iter()
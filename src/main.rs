@@ -2,15 +2,18 @@ mod commands;
 mod handler;
 mod http;
 mod scheduler;
+mod store;
 mod url;
 mod utils;
 
 use std::env;
+use std::sync::Arc;
 
 use serenity::framework::StandardFramework;
 use serenity::prelude::*;
 
 use crate::handler::Handler;
+use crate::utils::channel_cache::{ChannelCache, ChannelCacheKey};
 
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -31,7 +34,9 @@ async fn main() {
         }
     };
 
-    let intents = GatewayIntents::GUILD_MESSAGES
+    // GUILDS はチャンネル一覧のキャッシュを更新するのに使う
+    let intents = GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
@@ -49,6 +54,24 @@ async fn main() {
         }
     };
 
+    let http = client.cache_and_http.http.clone();
+    let channels = Arc::new(ChannelCache::new(http.clone()));
+    client
+        .data
+        .write()
+        .await
+        .insert::<ChannelCacheKey>(channels.clone());
+
+    // 保存先を開く。ファイルに保存する設定で初回なら db チャンネルから移行する
+    let store = match store::open(http, channels).await {
+        Ok(store) => store,
+        Err(why) => {
+            error!("Error opening store: {:?}", why);
+            return;
+        }
+    };
+    client.data.write().await.insert::<store::StoreKey>(store);
+
     if let Err(why) = client.start().await {
         error!("Client error: {:?}", why);
     }
@@ -3,7 +3,9 @@ use std::error::Error;
 use serenity::{async_trait, client::Context, model::id::ChannelId};
 use tracing::{error, info};
 
-use crate::utils::{fetch_atproto::Feed, get_db_channel::get_db_channel};
+use crate::store::{self, Source};
+use crate::utils::datetime::TimeZone;
+use crate::utils::fetch_atproto::Feed;
 
 use super::processer::Processer;
 
@@ -34,14 +36,16 @@ impl Processer<Feed> for ProcesserStruct {
             let avatar = item.post.author.avatar;
             let auther = item.post.author.display_name;
             let text = item.post.record.text;
+            // createdAt は UTC なので JST にして表示する
             let created_at = match chrono::NaiveDateTime::parse_from_str(
                 &item.post.record.createdAt,
                 "%Y-%m-%dT%H:%M:%S%.3fZ",
             ) {
-                Ok(created_at) => {
-                    let created_at = created_at + chrono::Duration::hours(9);
-                    created_at.format("%Y-%m-%d %H:%M:%S").to_string()
-                }
+                Ok(created_at) => created_at
+                    .and_utc()
+                    .with_timezone(&TimeZone::jst().fixed_offset())
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
                 Err(why) => {
                     error!("Error parsing created_at: {:?}", why);
                     return Err(Box::new(std::io::Error::new(
@@ -64,36 +68,15 @@ impl Processer<Feed> for ProcesserStruct {
     }
 
     async fn update_db_channel(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
-        let db_channel = match get_db_channel(&ctx).await {
-            Ok(db_channel) => db_channel,
-            Err(why) => {
-                error!("Error getting db channel: {:?}", why);
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "DBチャンネルが見つかりません。",
-                )));
-            }
-        };
-
-        let messages = db_channel
-            .messages(&ctx.http, |retriever| retriever.limit(100))
+        let now = chrono::Utc::now().naive_utc();
+        if let Err(why) = store::get(ctx)
             .await
-            .unwrap()
-            .into_iter()
-            .filter(|message| message.content.starts_with("atproto_last_date"))
-            .collect::<Vec<_>>();
-
-        for message in messages {
-            let _ = message.delete(&ctx.http).await;
+            .set_last_date(Source::Atproto, now)
+            .await
+        {
+            error!("Error updating last date: {:?}", why);
+            return Err(Box::new(why));
         }
-
-        let now = chrono::Utc::now();
-        let now = now.format("%Y-%m-%d %H:%M:%S").to_string();
-        let _ = db_channel
-            .send_message(&ctx.http, |m| {
-                m.content(format!("atproto_last_date {}", now))
-            })
-            .await;
         Ok(())
     }
 
@@ -1,3 +1,62 @@
 pub mod atproto;
 pub mod processer;
+pub mod reminder;
 pub mod rss;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serenity::client::Context;
+use tracing::{error, info};
+
+use self::processer::Processer;
+
+// todo の期限を確かめる間隔
+const REMINDER_INTERVAL: Duration = Duration::from_secs(60);
+// RSS feed と部分ツイートを取得する間隔
+const FEED_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+// ready は再接続のたびに呼ばれるので、タイマーは最初の 1 回だけ動かす
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+async fn fetch_feeds(ctx: &Context) {
+    // RSS feed を取得する
+    let rss_processor = rss::ProcesserStruct;
+    match rss_processor.run(ctx).await {
+        Ok(_) => info!("RSS feed fetched successfully."),
+        Err(why) => error!("Error fetching RSS feed: {:?}", why),
+    }
+
+    // 部分ツイートを取得する
+    let atproto_processor = atproto::ProcesserStruct;
+    match atproto_processor.run(ctx).await {
+        Ok(_) => info!("部分ツイート fetched successfully."),
+        Err(why) => error!("Error fetching 部分ツイート: {:?}", why),
+    }
+}
+
+/// 定期的に動かす処理をバックグラウンドで始める。どれも起動した直後に 1 回目を実行する
+pub fn spawn(ctx: Context) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let reminder_ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(why) = reminder::ProcesserStruct.run(&reminder_ctx).await {
+                error!("Error sending todo reminders: {:?}", why);
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FEED_INTERVAL);
+        loop {
+            interval.tick().await;
+            fetch_feeds(&ctx).await;
+        }
+    });
+}
@@ -0,0 +1,230 @@
+use std::error::Error;
+
+use chrono::Utc;
+use serenity::{
+    async_trait,
+    client::Context,
+    http::error::Error as HttpError,
+    model::id::{ChannelId, UserId},
+    Error as SerenityError,
+};
+use tracing::{error, info, warn};
+
+use crate::store::{self, Todo};
+
+use super::processer::Processer;
+
+// 期限のどれくらい前に知らせるか (秒)
+const REMIND_BEFORE: i64 = 60 * 60;
+
+pub(crate) struct ProcesserStruct;
+
+// 本文はこの文字数までにして、メッセージの上限 (2000 文字) を超えないようにする
+const MAX_TEXT_CHARS: usize = 1000;
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+// 知らせる相手。担当者、いなければ作った人
+fn recipients(todo: &Todo) -> Vec<u64> {
+    match (todo.assignees.is_empty(), todo.creator) {
+        (true, Some(creator)) => vec![creator],
+        _ => todo.assignees.clone(),
+    }
+}
+
+fn reminder_text(todo: &Todo, now: i64) -> String {
+    let mentions = recipients(todo)
+        .iter()
+        .map(|id| format!("<@{}> ", id))
+        .collect::<String>();
+    let text = truncate(&todo.text, MAX_TEXT_CHARS);
+    let due = todo.due.unwrap_or_default();
+    let content = if todo.is_overdue(now) {
+        format!(
+            "{}TODO {} {} の期限 (<t:{}:f>) を過ぎています。",
+            mentions, todo.id, text, due
+        )
+    } else {
+        format!(
+            "{}TODO {} {} の期限は <t:{}:R> です。",
+            mentions, todo.id, text, due
+        )
+    };
+    // 担当者が多すぎるときも上限に収める
+    truncate(&content, MAX_MESSAGE_CHARS - 1)
+}
+
+// 送り直しても届かない失敗。チャンネルが消えた、権限がない、内容が不正など。
+// 混み合っているときやつながらないときは次の実行で送り直す
+fn is_permanent(why: &SerenityError) -> bool {
+    match why {
+        SerenityError::Http(http) => match http.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                let status = response.status_code.as_u16();
+                (400..500).contains(&status) && status != 429
+            }
+            _ => false,
+        },
+        SerenityError::Model(_) => true,
+        _ => false,
+    }
+}
+
+// 本文の @everyone やロールのメンションでは通知せず、知らせる相手だけをメンションする
+async fn send(
+    ctx: &Context,
+    channel: ChannelId,
+    content: &str,
+    users: &[u64],
+) -> Result<(), SerenityError> {
+    channel
+        .send_message(&ctx.http, |m| {
+            m.content(content).allowed_mentions(|mentions| {
+                mentions
+                    .empty_parse()
+                    .users(users.iter().map(|&id| UserId(id)))
+            })
+        })
+        .await
+        .map(|_| ())
+}
+
+async fn send_dm(
+    ctx: &Context,
+    user: u64,
+    content: &str,
+    users: &[u64],
+) -> Result<(), SerenityError> {
+    let channel = UserId(user).create_dm_channel(&ctx.http).await?;
+    send(ctx, channel.id, content, users).await
+}
+
+#[async_trait]
+impl Processer<Todo> for ProcesserStruct {
+    // 未完了で、期限が近いか過ぎていて、まだ知らせていない todo
+    async fn fetch(&self, ctx: &Context) -> Result<Vec<Todo>, Box<dyn Error>> {
+        let now = Utc::now().timestamp();
+        let todos = store::get(ctx).await.todos().await?;
+        Ok(todos
+            .into_iter()
+            .filter(|todo| todo.done.is_none() && !todo.reminded)
+            .filter(|todo| todo.due.is_some_and(|due| due - REMIND_BEFORE <= now))
+            .collect())
+    }
+
+    // 追加したチャンネルに投稿する。チャンネルが分からないか投稿できなければ作った人に DM を送る
+    async fn post_to_channel(&self, ctx: &Context, items: Vec<Todo>) -> Result<(), Box<dyn Error>> {
+        let now = Utc::now().timestamp();
+        let store = store::get(ctx).await;
+        for todo in items {
+            let content = reminder_text(&todo, now);
+            let users = recipients(&todo);
+            let sent = match (todo.channel, todo.creator) {
+                (Some(channel), creator) => {
+                    match (
+                        send(ctx, ChannelId(channel), &content, &users).await,
+                        creator,
+                    ) {
+                        (Err(why), Some(creator)) if is_permanent(&why) => {
+                            warn!(
+                                "Cannot send todo reminder {} to channel {}: {:?}",
+                                todo.id, channel, why
+                            );
+                            send_dm(ctx, creator, &content, &users).await
+                        }
+                        (sent, _) => sent,
+                    }
+                }
+                (None, Some(creator)) => send_dm(ctx, creator, &content, &users).await,
+                (None, None) => {
+                    warn!("No destination for todo reminder: {}", todo.id);
+                    Ok(())
+                }
+            };
+            match sent {
+                Ok(()) => {}
+                // 届かない通知を毎回送り直さないよう、諦めて印を付ける
+                Err(why) if is_permanent(&why) => {
+                    error!("Giving up todo reminder {}: {:?}", todo.id, why);
+                }
+                Err(why) => {
+                    error!("Error sending todo reminder: {:?}", why);
+                    continue;
+                }
+            }
+
+            // 次の実行で同じ通知を送らないよう、1 件ずつ印を付ける。
+            // 読み出した後の編集を戻さないよう、印だけを付け直す
+            let due = todo.due.unwrap_or_default();
+            if let Err(why) = store.mark_reminded(todo.id, due).await {
+                error!("Error marking todo as reminded: {:?}", why);
+            }
+        }
+        Ok(())
+    }
+
+    // 通知済みの印は post_to_channel で付けるので、ここでは何もしない
+    async fn update_db_channel(&self, _ctx: &Context) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    async fn run(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
+        let items = self.fetch(ctx).await?;
+        if items.is_empty() {
+            return Ok(());
+        }
+        info!("Sending {} todo reminders.", items.len());
+        self.post_to_channel(ctx, items).await?;
+        self.update_db_channel(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reminder_text() {
+        let todo = Todo {
+            creator: Some(7),
+            due: Some(1_792_400_000),
+            ..Todo::new(3, "review".to_string())
+        };
+        assert_eq!(
+            reminder_text(&todo, 1_792_399_000),
+            "<@7> TODO 3 review の期限は <t:1792400000:R> です。"
+        );
+        let todo = Todo {
+            assignees: vec![42, 43],
+            ..todo
+        };
+        assert_eq!(
+            reminder_text(&todo, 1_792_400_001),
+            "<@42> <@43> TODO 3 review の期限 (<t:1792400000:f>) を過ぎています。"
+        );
+
+        // 長い本文は切り詰めて、メッセージの上限に収める
+        let todo = Todo {
+            text: "a".repeat(5000),
+            ..todo
+        };
+        let text = reminder_text(&todo, 1_792_400_001);
+        assert!(text.chars().count() < 2000);
+        assert!(text.contains("a…"));
+    }
+
+    #[test]
+    fn test_is_permanent() {
+        use serenity::model::error::Error as ModelError;
+        assert!(is_permanent(&SerenityError::Model(
+            ModelError::MessageTooLong(10)
+        )));
+        assert!(!is_permanent(&SerenityError::Other("timeout")));
+    }
+}
@@ -1,13 +1,24 @@
+use std::collections::HashSet;
 use std::error::Error;
 
 use rss::Item;
 use serenity::{async_trait, client::Context, model::id::ChannelId};
 use tracing::{error, info, warn};
 
-use crate::utils::{fetch_rss_feed::fetch_rss_feed, get_db_channel::get_db_channel};
+use crate::store::{self, Source};
+use crate::url::url::Url;
+use crate::utils::fetch_rss_feed::fetch_rss_feed;
 
 use super::processer::Processer;
 
+// 投稿済みか確かめるために読む投稿先のメッセージの数。取得は last_date 以降の記事だけなので、直近の分で足りる
+const RECENT_MESSAGES: u64 = 100;
+
+// 投稿済みのリンクと比較するための正規形
+fn canonical_link(link: &str) -> String {
+    Url::parse(link.trim()).canonicalize(true).to_string()
+}
+
 pub(crate) struct ProcesserStruct;
 
 #[async_trait]
@@ -18,9 +29,21 @@ impl Processer<Item> for ProcesserStruct {
 
     async fn post_to_channel(&self, ctx: &Context, items: Vec<Item>) -> Result<(), Box<dyn Error>> {
         let channel = ChannelId(1208611584964825099);
+        // 複数の feed や前回の取得と同じ記事が載っていることがあるので、正規形が同じリンクは 1 回だけ投稿する。
+        // 投稿済みのリンクは投稿先のチャンネルに残っているので、直近のメッセージから読む
+        let recent = channel
+            .messages(&ctx.http, |retriever| retriever.limit(RECENT_MESSAGES))
+            .await?;
+        let mut posted = recent
+            .iter()
+            .map(|message| canonical_link(&message.content))
+            .collect::<HashSet<_>>();
         for item in items {
             let _ = match item.link {
                 Some(link) => {
+                    if !posted.insert(canonical_link(&link)) {
+                        continue;
+                    }
                     let _ = channel.send_message(&ctx.http, |m| m.content(link)).await;
                 }
                 None => {
@@ -32,34 +55,11 @@ impl Processer<Item> for ProcesserStruct {
     }
 
     async fn update_db_channel(&self, ctx: &Context) -> Result<(), Box<dyn Error>> {
-        let db_channel = match get_db_channel(&ctx).await {
-            Ok(db_channel) => db_channel,
-            Err(why) => {
-                error!("Error getting db channel: {:?}", why);
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "DBチャンネルが見つかりません。",
-                )));
-            }
-        };
-
-        let messages = db_channel
-            .messages(&ctx.http, |retriever| retriever.limit(100))
-            .await
-            .unwrap()
-            .into_iter()
-            .filter(|message| message.content.starts_with("rss_last_date"))
-            .collect::<Vec<_>>();
-
-        for message in messages {
-            let _ = message.delete(&ctx.http).await;
+        let now = chrono::Utc::now().naive_utc();
+        if let Err(why) = store::get(ctx).await.set_last_date(Source::Rss, now).await {
+            error!("Error updating last date: {:?}", why);
+            return Err(Box::new(why));
         }
-
-        let now = chrono::Utc::now();
-        let now = now.format("%Y-%m-%d %H:%M:%S").to_string();
-        let _ = db_channel
-            .send_message(&ctx.http, |m| m.content(format!("rss_last_date {}", now)))
-            .await;
         Ok(())
     }
 
@@ -0,0 +1,649 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    http::Http,
+    model::{
+        channel::Message,
+        event::MessageUpdateEvent,
+        id::{ChannelId, MessageId, UserId},
+    },
+};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use super::record;
+use super::{next_todo_id, Source, Store, Todo};
+use crate::utils::channel_cache::ChannelCache;
+use crate::utils::get_db_channel::get_db_channel;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// 1 回の取得で読めるメッセージの上限
+const PAGE_SIZE: u64 = 100;
+// 1 メッセージに書ける文字数
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+const TODO_PREFIX: &str = "todo_message";
+const TODO_COUNTER_PREFIX: &str = "todo_counter";
+const RSS_LINK_PREFIX: &str = "rss_link";
+
+fn last_date_prefix(source: Source) -> &'static str {
+    match source {
+        Source::Rss => "rss_last_date",
+        Source::Atproto => "atproto_last_date",
+    }
+}
+
+// 次に振る todo の id。todo を消しても戻さない
+#[derive(Serialize, Deserialize)]
+struct TodoCounterRecord {
+    next_id: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RssLinkRecord {
+    link: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LastDateRecord {
+    // %Y-%m-%d %H:%M:%S
+    date: String,
+}
+
+impl LastDateRecord {
+    fn new(date: NaiveDateTime) -> Self {
+        LastDateRecord {
+            date: date.format(DATE_FORMAT).to_string(),
+        }
+    }
+
+    fn date(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.date, DATE_FORMAT).ok()
+    }
+}
+
+// 以下はバージョンのない古い形式。prefix より後ろを受け取る
+
+// `todo_message {id} {text}`
+fn legacy_todo(rest: &str) -> Option<Todo> {
+    let (id, text) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some(Todo::new(id.parse().ok()?, text.trim().to_string()))
+}
+
+// `rss_link {link}`
+fn legacy_rss_link(rest: &str) -> Option<RssLinkRecord> {
+    let link = rest.split_whitespace().next()?;
+    Some(RssLinkRecord {
+        link: link.to_string(),
+    })
+}
+
+// `rss_last_date %Y-%m-%d %H:%M:%S`
+fn legacy_last_date(rest: &str) -> Option<LastDateRecord> {
+    let date = NaiveDateTime::parse_from_str(rest.trim(), DATE_FORMAT).ok()?;
+    Some(LastDateRecord::new(date))
+}
+
+fn request_error(what: &str) -> io::Error {
+    io::Error::other(format!("{}に失敗しました", what))
+}
+
+// db チャンネルのメッセージを prefix (最初の単語) ごとにまとめたもの
+struct Index {
+    channel_id: ChannelId,
+    // この bot のユーザー
+    bot_id: UserId,
+    by_prefix: HashMap<String, BTreeMap<MessageId, String>>,
+    // 古い形式でも書き直さないメッセージ。一度書き直そうとしたものと、bot 以外が書いたもの
+    settled: HashSet<MessageId>,
+}
+
+impl Index {
+    fn new(channel_id: ChannelId, bot_id: UserId) -> Self {
+        Index {
+            channel_id,
+            bot_id,
+            by_prefix: HashMap::new(),
+            settled: HashSet::new(),
+        }
+    }
+
+    // 書き直すのはメッセージごとに 1 回だけにする。失敗したものを読むたびに試さない
+    fn should_upgrade(&mut self, id: MessageId) -> bool {
+        self.settled.insert(id)
+    }
+
+    fn insert(&mut self, id: MessageId, content: String) {
+        // 編集で prefix が変わることもあるので一度消す
+        self.remove(id);
+        let prefix = content.split_whitespace().next().unwrap_or("").to_string();
+        self.by_prefix
+            .entry(prefix)
+            .or_default()
+            .insert(id, content);
+    }
+
+    // 読んだメッセージを入れる。bot 以外が書いたものは書き直せない
+    fn insert_message(&mut self, message: &Message) {
+        if message.author.id != self.bot_id {
+            self.settled.insert(message.id);
+        }
+        self.insert(message.id, message.content.clone());
+    }
+
+    fn remove(&mut self, id: MessageId) {
+        for messages in self.by_prefix.values_mut() {
+            messages.remove(&id);
+        }
+    }
+
+    // 新しいものから順に返す
+    fn get(&self, prefix: &str) -> Vec<(MessageId, &str)> {
+        match self.by_prefix.get(prefix) {
+            Some(messages) => messages
+                .iter()
+                .rev()
+                .map(|(id, content)| (*id, content.as_str()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+// db チャンネルに 1 件 1 メッセージで保存する。
+// 最初に全履歴を読んで index を作り、以降は自分の書き込みと gateway のイベントで更新する
+pub struct ChannelStore {
+    http: Arc<Http>,
+    channels: Arc<ChannelCache>,
+    index: Mutex<Option<Index>>,
+    // todo の書き込みを 1 つずつにして、同じ id を振らないようにする
+    todo_lock: Mutex<()>,
+}
+
+impl ChannelStore {
+    pub fn new(http: Arc<Http>, channels: Arc<ChannelCache>) -> Self {
+        ChannelStore {
+            http,
+            channels,
+            index: Mutex::new(None),
+            todo_lock: Mutex::new(()),
+        }
+    }
+
+    // before を動かしながら古い方へ全履歴を読む
+    async fn load(&self, channel_id: ChannelId) -> io::Result<Index> {
+        let bot_id = match self.http.get_current_user().await {
+            Ok(user) => user.id,
+            Err(why) => {
+                error!("Error getting current user: {:?}", why);
+                return Err(request_error("bot のユーザーの取得"));
+            }
+        };
+        let mut index = Index::new(channel_id, bot_id);
+        let mut before = None;
+        loop {
+            let page = channel_id
+                .messages(&self.http, |retriever| {
+                    if let Some(before) = before {
+                        retriever.before(before);
+                    }
+                    retriever.limit(PAGE_SIZE)
+                })
+                .await;
+            let page = match page {
+                Ok(page) => page,
+                Err(why) => {
+                    error!("Error getting db channel messages: {:?}", why);
+                    return Err(request_error("メッセージの取得"));
+                }
+            };
+            // 新しい順に返ってくるので、最後のものが一番古い
+            before = page.last().map(|message| message.id);
+            let len = page.len() as u64;
+            for message in &page {
+                index.insert_message(message);
+            }
+            if len < PAGE_SIZE {
+                break;
+            }
+        }
+        info!(
+            "loaded db channel index: {} messages",
+            index.by_prefix.values().map(|m| m.len()).sum::<usize>()
+        );
+        Ok(index)
+    }
+
+    // 自分の書き込みを index に反映する
+    async fn update_index(&self, channel_id: ChannelId, f: impl FnOnce(&mut Index)) {
+        let mut index = self.index.lock().await;
+        if let Some(index) = index
+            .as_mut()
+            .filter(|index| index.channel_id == channel_id)
+        {
+            f(index);
+        }
+    }
+
+    async fn channel_id(&self) -> io::Result<ChannelId> {
+        match get_db_channel(&self.channels).await {
+            Ok(channel) => Ok(channel.id),
+            Err(why) => Err(io::Error::new(io::ErrorKind::NotFound, why.to_string())),
+        }
+    }
+
+    // prefix のメッセージのうち読めたものを新しい順に返す。形式の合わないメッセージは読み飛ばす。
+    // 古い形式で書かれていたものは、返事を待たせないよう裏で今の形式に書き直す
+    async fn records<T: Serialize + DeserializeOwned>(
+        &self,
+        prefix: &str,
+        legacy: impl Fn(&str) -> Option<T>,
+    ) -> io::Result<(ChannelId, Vec<(MessageId, T)>)> {
+        let mut guard = self.index.lock().await;
+        if guard.is_none() {
+            let channel_id = self.channel_id().await?;
+            *guard = Some(self.load(channel_id).await?);
+        }
+        let index = guard.as_mut().unwrap();
+        let channel_id = index.channel_id;
+        let mut records = Vec::new();
+        let mut upgrades = Vec::new();
+        for (id, content) in index.get(prefix) {
+            match record::decode(prefix, content, &legacy) {
+                Ok(decoded) => {
+                    if decoded.legacy {
+                        upgrades.push((id, record::encode(prefix, &decoded.value)));
+                    }
+                    records.push((id, decoded.value));
+                }
+                Err(why) => warn!("Skipping db channel message {}: {}", id, why),
+            }
+        }
+        upgrades.retain(|(id, content)| {
+            index.should_upgrade(*id) && content.chars().count() <= MAX_MESSAGE_CHARS
+        });
+        drop(guard);
+
+        // 書き直したメッセージは gateway の更新のイベントで index に反映される
+        if !upgrades.is_empty() {
+            let http = self.http.clone();
+            tokio::spawn(async move {
+                for (id, content) in upgrades {
+                    match channel_id
+                        .edit_message(&http, id, |m| m.content(content))
+                        .await
+                    {
+                        Ok(_) => info!("upgraded db channel message {}", id),
+                        Err(why) => warn!("Error upgrading db channel message {}: {:?}", id, why),
+                    }
+                }
+            });
+        }
+        Ok((channel_id, records))
+    }
+
+    async fn send(&self, channel_id: ChannelId, content: String) -> io::Result<()> {
+        match channel_id.say(&self.http, content).await {
+            Ok(message) => {
+                self.update_index(channel_id, |index| {
+                    index.insert(message.id, message.content)
+                })
+                .await;
+                Ok(())
+            }
+            Err(why) => {
+                error!("Error sending db channel message: {:?}", why);
+                Err(request_error("メッセージの送信"))
+            }
+        }
+    }
+
+    async fn edit(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> io::Result<()> {
+        match channel_id
+            .edit_message(&self.http, message_id, |m| m.content(content))
+            .await
+        {
+            Ok(message) => {
+                self.update_index(channel_id, |index| {
+                    index.insert(message.id, message.content)
+                })
+                .await;
+                Ok(())
+            }
+            Err(why) => {
+                error!("Error editing db channel message: {:?}", why);
+                Err(request_error("メッセージの編集"))
+            }
+        }
+    }
+
+    // 記録してある counter とそのメッセージ。まだなければ 0
+    async fn todo_counter(&self) -> io::Result<(ChannelId, Option<MessageId>, u64)> {
+        let (channel_id, records) = self
+            .records(TODO_COUNTER_PREFIX, |_| None::<TodoCounterRecord>)
+            .await?;
+        Ok(match records.into_iter().next() {
+            Some((message_id, record)) => (channel_id, Some(message_id), record.next_id),
+            None => (channel_id, None, 0),
+        })
+    }
+
+    async fn delete(&self, channel_id: ChannelId, message_id: MessageId) -> io::Result<()> {
+        match channel_id.delete_message(&self.http, message_id).await {
+            Ok(_) => {
+                self.update_index(channel_id, |index| index.remove(message_id))
+                    .await;
+                Ok(())
+            }
+            Err(why) => {
+                error!("Error deleting db channel message: {:?}", why);
+                Err(request_error("メッセージの削除"))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Store for ChannelStore {
+    async fn todos(&self) -> io::Result<Vec<Todo>> {
+        let (_, records) = self.records(TODO_PREFIX, legacy_todo).await?;
+        Ok(records.into_iter().map(|(_, todo)| todo).collect())
+    }
+
+    async fn add_todo(&self, todo: Todo) -> io::Result<Todo> {
+        let _lock = self.todo_lock.lock().await;
+        let todos = self.todos().await?;
+        let (channel_id, counter_id, counter) = self.todo_counter().await?;
+        let todo = Todo {
+            id: next_todo_id(counter, &todos),
+            ..todo
+        };
+
+        // 先に counter を進める。todo の送信に失敗しても欠番になるだけで、同じ id は振らない
+        let counter = record::encode(
+            TODO_COUNTER_PREFIX,
+            &TodoCounterRecord {
+                next_id: todo.id + 1,
+            },
+        );
+        match counter_id {
+            Some(message_id) => self.edit(channel_id, message_id, counter).await?,
+            None => self.send(channel_id, counter).await?,
+        }
+        self.send(channel_id, record::encode(TODO_PREFIX, &todo))
+            .await?;
+        Ok(todo)
+    }
+
+    async fn next_todo_id(&self) -> io::Result<u64> {
+        let todos = self.todos().await?;
+        let (_, _, counter) = self.todo_counter().await?;
+        Ok(next_todo_id(counter, &todos))
+    }
+
+    async fn update_todo(&self, todo: Todo) -> io::Result<bool> {
+        let _lock = self.todo_lock.lock().await;
+        let (channel_id, records) = self.records(TODO_PREFIX, legacy_todo).await?;
+        match records.into_iter().find(|(_, t)| t.id == todo.id) {
+            Some((message_id, _)) => self
+                .edit(channel_id, message_id, record::encode(TODO_PREFIX, &todo))
+                .await
+                .map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    async fn remove_todo(&self, id: u64) -> io::Result<bool> {
+        let _lock = self.todo_lock.lock().await;
+        let (channel_id, records) = self.records(TODO_PREFIX, legacy_todo).await?;
+        match records.into_iter().find(|(_, todo)| todo.id == id) {
+            Some((message_id, _)) => self.delete(channel_id, message_id).await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    async fn replace_todo(
+        &self,
+        id: u64,
+        expected: Option<&Todo>,
+        todo: Option<Todo>,
+    ) -> io::Result<bool> {
+        let _lock = self.todo_lock.lock().await;
+        let (channel_id, records) = self.records(TODO_PREFIX, legacy_todo).await?;
+        let current = records.into_iter().find(|(_, todo)| todo.id == id);
+        if current.as_ref().map(|(_, todo)| todo) != expected {
+            return Ok(false);
+        }
+        match (current, todo) {
+            (Some((message_id, _)), Some(todo)) => {
+                self.edit(channel_id, message_id, record::encode(TODO_PREFIX, &todo))
+                    .await?
+            }
+            (Some((message_id, _)), None) => self.delete(channel_id, message_id).await?,
+            (None, Some(todo)) => {
+                self.send(channel_id, record::encode(TODO_PREFIX, &todo))
+                    .await?
+            }
+            (None, None) => {}
+        }
+        Ok(true)
+    }
+
+    async fn mark_reminded(&self, id: u64, due: i64) -> io::Result<bool> {
+        let _lock = self.todo_lock.lock().await;
+        let (channel_id, records) = self.records(TODO_PREFIX, legacy_todo).await?;
+        match records.into_iter().find(|(_, todo)| todo.id == id) {
+            Some((message_id, todo)) if todo.still_due(due) => {
+                let todo = Todo {
+                    reminded: true,
+                    ..todo
+                };
+                self.edit(channel_id, message_id, record::encode(TODO_PREFIX, &todo))
+                    .await
+                    .map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn rss_links(&self) -> io::Result<Vec<String>> {
+        let (_, records) = self.records(RSS_LINK_PREFIX, legacy_rss_link).await?;
+        Ok(records.into_iter().map(|(_, record)| record.link).collect())
+    }
+
+    async fn add_rss_link(&self, link: String) -> io::Result<()> {
+        let channel_id = self.channel_id().await?;
+        self.send(
+            channel_id,
+            record::encode(RSS_LINK_PREFIX, &RssLinkRecord { link }),
+        )
+        .await
+    }
+
+    async fn remove_rss_link(&self, link: &str) -> io::Result<bool> {
+        let (channel_id, records) = self.records(RSS_LINK_PREFIX, legacy_rss_link).await?;
+        match records.into_iter().find(|(_, record)| record.link == link) {
+            Some((message_id, _)) => self.delete(channel_id, message_id).await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    async fn last_date(&self, source: Source) -> io::Result<Option<NaiveDateTime>> {
+        let (_, records) = self
+            .records(last_date_prefix(source), legacy_last_date)
+            .await?;
+        // last_date は基本的に 1 つしか存在しないはずなので、最新のものを使う
+        Ok(records.into_iter().find_map(|(_, record)| record.date()))
+    }
+
+    // 古い日時は消してから書き込む
+    async fn set_last_date(&self, source: Source, date: NaiveDateTime) -> io::Result<()> {
+        let (channel_id, records) = self
+            .records(last_date_prefix(source), legacy_last_date)
+            .await?;
+        for (message_id, _) in records {
+            self.delete(channel_id, message_id).await?;
+        }
+        let content = record::encode(last_date_prefix(source), &LastDateRecord::new(date));
+        self.send(channel_id, content).await
+    }
+
+    async fn message_created(&self, message: &Message) {
+        self.update_index(message.channel_id, |index| index.insert_message(message))
+            .await;
+    }
+
+    async fn message_updated(&self, event: &MessageUpdateEvent) {
+        if let Some(content) = &event.content {
+            self.update_index(event.channel_id, |index| {
+                index.insert(event.id, content.clone())
+            })
+            .await;
+        }
+    }
+
+    async fn messages_deleted(&self, channel_id: ChannelId, message_ids: &[MessageId]) {
+        self.update_index(channel_id, |index| {
+            for id in message_ids {
+                index.remove(*id);
+            }
+        })
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        let mut index = Index::new(ChannelId(1), UserId(2));
+        index.insert(MessageId(10), "todo_message 1 a".to_string());
+        index.insert(MessageId(30), "todo_message 2 b".to_string());
+        index.insert(MessageId(20), "rss_link https://a".to_string());
+        assert_eq!(
+            index.get("todo_message"),
+            vec![
+                (MessageId(30), "todo_message 2 b"),
+                (MessageId(10), "todo_message 1 a")
+            ]
+        );
+
+        // 編集で prefix が変わったら元の prefix からは消える
+        index.insert(MessageId(30), "rss_link https://b".to_string());
+        assert_eq!(
+            index.get("todo_message"),
+            vec![(MessageId(10), "todo_message 1 a")]
+        );
+        assert_eq!(index.get("rss_link").len(), 2);
+
+        index.remove(MessageId(10));
+        index.remove(MessageId(99));
+        assert_eq!(index.get("todo_message"), Vec::new());
+        assert_eq!(index.get("rss_last_date"), Vec::new());
+
+        // 古い形式の書き直しは失敗しても 1 回しか試さない
+        assert!(index.should_upgrade(MessageId(30)));
+        assert!(!index.should_upgrade(MessageId(30)));
+    }
+
+    #[test]
+    fn test_max_todo_record() {
+        use super::super::{Completion, MAX_ASSIGNEES, MAX_TAGS, MAX_TEXT_CHARS};
+
+        // どのフィールドも最も長くなる値でも 1 メッセージに収まる
+        let todo = Todo {
+            id: u64::MAX,
+            done: Some(Completion {
+                at: i64::MIN,
+                by: u64::MAX,
+            }),
+            creator: Some(u64::MAX),
+            assignees: vec![u64::MAX; MAX_ASSIGNEES],
+            due: Some(i64::MIN),
+            reminded: true,
+            channel: Some(u64::MAX),
+            tags: vec!["あ".repeat(24); MAX_TAGS],
+            priority: Some(3),
+            ..Todo::new(0, "\"".repeat(MAX_TEXT_CHARS / 2))
+        };
+        assert_eq!(todo.check_size(), Ok(()));
+        assert!(record::encode(TODO_PREFIX, &todo).chars().count() <= MAX_MESSAGE_CHARS);
+
+        let long = Todo {
+            text: format!("{}a", todo.text),
+            ..todo.clone()
+        };
+        assert_eq!(
+            long.check_size(),
+            Err("本文は 1000 文字までです".to_string())
+        );
+        let crowded = Todo {
+            assignees: vec![1; MAX_ASSIGNEES + 1],
+            ..todo
+        };
+        assert_eq!(
+            crowded.check_size(),
+            Err("担当者は 10 人までです".to_string())
+        );
+    }
+
+    #[test]
+    fn test_legacy_todo() {
+        let decoded = record::decode(TODO_PREFIX, "todo_message 12 牛乳 を買う", legacy_todo);
+        assert_eq!(
+            decoded,
+            Ok(record::Decoded {
+                value: Todo::new(12, "牛乳 を買う".to_string()),
+                legacy: true
+            })
+        );
+        // 書き直した後は今の形式として読める
+        let content = record::encode(TODO_PREFIX, &decoded.unwrap().value);
+        assert_eq!(content, r#"todo_message v1 {"id":12,"text":"牛乳 を買う"}"#);
+        assert_eq!(
+            record::decode(TODO_PREFIX, &content, legacy_todo).map(|d| d.legacy),
+            Ok(false)
+        );
+
+        assert_eq!(legacy_todo("3"), Some(Todo::new(3, "".to_string())));
+        assert_eq!(legacy_todo("abc text"), None);
+        assert_eq!(legacy_todo(""), None);
+    }
+
+    #[test]
+    fn test_legacy_rss_link() {
+        assert_eq!(
+            legacy_rss_link("https://example.com/feed").map(|record| record.link),
+            Some("https://example.com/feed".to_string())
+        );
+        assert!(legacy_rss_link("").is_none());
+    }
+
+    #[test]
+    fn test_legacy_last_date() {
+        let date = NaiveDateTime::parse_from_str("2026-10-18 09:30:00", DATE_FORMAT).unwrap();
+        assert_eq!(
+            legacy_last_date("2026-10-18 09:30:00").and_then(|record| record.date()),
+            Some(date)
+        );
+        assert!(legacy_last_date("").is_none());
+        assert!(legacy_last_date("https://a").is_none());
+        assert_eq!(
+            record::encode("rss_last_date", &LastDateRecord::new(date)),
+            r#"rss_last_date v1 {"date":"2026-10-18 09:30:00"}"#
+        );
+    }
+}
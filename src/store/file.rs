@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+
+use super::{next_todo_id, Source, Store, Todo};
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// ファイルに保存する内容。手で読み書きしやすいよう JSON にする
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Data {
+    pub todos: Vec<Todo>,
+    // 古いファイルにはないので、その場合は今ある todo から決める
+    #[serde(default)]
+    pub next_todo_id: u64,
+    pub rss_links: Vec<String>,
+    // 取得元の名前から %Y-%m-%d %H:%M:%S 形式の日時
+    pub last_dates: BTreeMap<String, String>,
+}
+
+impl Data {
+    pub fn last_date(&self, source: Source) -> Option<NaiveDateTime> {
+        let date = self.last_dates.get(source.name())?;
+        NaiveDateTime::parse_from_str(date, DATE_FORMAT).ok()
+    }
+
+    pub fn set_last_date(&mut self, source: Source, date: NaiveDateTime) {
+        self.last_dates.insert(
+            source.name().to_string(),
+            date.format(DATE_FORMAT).to_string(),
+        );
+    }
+}
+
+// 全体をメモリに持ち、変更のたびにファイル全体を書き直す
+pub struct FileStore {
+    path: PathBuf,
+    data: Mutex<Data>,
+}
+
+impl FileStore {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let text = fs::read_to_string(&path)?;
+        let data = serde_json::from_str(&text).map_err(|why| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} を読めません: {}", path.display(), why),
+            )
+        })?;
+        Ok(FileStore {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    pub fn create(path: impl Into<PathBuf>, data: Data) -> io::Result<Self> {
+        let store = FileStore {
+            path: path.into(),
+            data: Mutex::new(Data::default()),
+        };
+        store.save(&data)?;
+        *store.lock() = data;
+        Ok(store)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Data> {
+        // 書き込み中に panic しても中身はファイルと同じなので使い続ける
+        self.data
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // 途中で落ちても壊れたファイルが残らないよう、別のファイルに書いてから置き換える
+    fn save(&self, data: &Data) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(data)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    // data を変更して保存する。保存に失敗したら変更しない
+    fn modify<T>(&self, f: impl FnOnce(&mut Data) -> T) -> io::Result<T> {
+        let mut data = self.lock();
+        let mut next = data.clone();
+        let result = f(&mut next);
+        self.save(&next)?;
+        *data = next;
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Store for FileStore {
+    async fn todos(&self) -> io::Result<Vec<Todo>> {
+        Ok(self.lock().todos.clone())
+    }
+
+    async fn add_todo(&self, todo: Todo) -> io::Result<Todo> {
+        self.modify(|data| {
+            let todo = Todo {
+                id: next_todo_id(data.next_todo_id, &data.todos),
+                ..todo
+            };
+            data.next_todo_id = todo.id + 1;
+            data.todos.push(todo.clone());
+            todo
+        })
+    }
+
+    async fn next_todo_id(&self) -> io::Result<u64> {
+        let data = self.lock();
+        Ok(next_todo_id(data.next_todo_id, &data.todos))
+    }
+
+    async fn update_todo(&self, todo: Todo) -> io::Result<bool> {
+        self.modify(
+            |data| match data.todos.iter_mut().find(|t| t.id == todo.id) {
+                Some(found) => {
+                    *found = todo;
+                    true
+                }
+                None => false,
+            },
+        )
+    }
+
+    async fn remove_todo(&self, id: u64) -> io::Result<bool> {
+        self.modify(|data| {
+            let len = data.todos.len();
+            data.todos.retain(|todo| todo.id != id);
+            data.todos.len() != len
+        })
+    }
+
+    async fn replace_todo(
+        &self,
+        id: u64,
+        expected: Option<&Todo>,
+        todo: Option<Todo>,
+    ) -> io::Result<bool> {
+        self.modify(|data| {
+            let index = data.todos.iter().position(|t| t.id == id);
+            if index.map(|index| &data.todos[index]) != expected {
+                return false;
+            }
+            match (index, todo) {
+                (Some(index), Some(todo)) => data.todos[index] = todo,
+                (Some(index), None) => {
+                    data.todos.remove(index);
+                }
+                (None, Some(todo)) => {
+                    // id 順に並べておく
+                    let index = data.todos.partition_point(|t| t.id < todo.id);
+                    data.todos.insert(index, todo);
+                }
+                (None, None) => {}
+            }
+            true
+        })
+    }
+
+    async fn mark_reminded(&self, id: u64, due: i64) -> io::Result<bool> {
+        self.modify(|data| {
+            match data
+                .todos
+                .iter_mut()
+                .find(|todo| todo.id == id && todo.still_due(due))
+            {
+                Some(todo) => {
+                    todo.reminded = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    async fn rss_links(&self) -> io::Result<Vec<String>> {
+        Ok(self.lock().rss_links.clone())
+    }
+
+    async fn add_rss_link(&self, link: String) -> io::Result<()> {
+        self.modify(|data| data.rss_links.push(link))
+    }
+
+    async fn remove_rss_link(&self, link: &str) -> io::Result<bool> {
+        self.modify(|data| match data.rss_links.iter().position(|l| l == link) {
+            Some(index) => {
+                data.rss_links.remove(index);
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn last_date(&self, source: Source) -> io::Result<Option<NaiveDateTime>> {
+        Ok(self.lock().last_date(source))
+    }
+
+    async fn set_last_date(&self, source: Source, date: NaiveDateTime) -> io::Result<()> {
+        self.modify(|data| data.set_last_date(source, date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rinton_store_{}_{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn todo(id: u64, text: &str) -> Todo {
+        Todo::new(id, text.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_file_store() {
+        let path = temp_path("roundtrip");
+        let store = FileStore::create(&path, Data::default()).unwrap();
+        assert_eq!(
+            store.add_todo(todo(0, "牛乳を買う")).await.unwrap(),
+            todo(1, "牛乳を買う")
+        );
+        store.add_todo(todo(0, "review")).await.unwrap();
+        assert!(store.update_todo(todo(2, "review PR")).await.unwrap());
+        assert!(!store.update_todo(todo(3, "none")).await.unwrap());
+        assert!(store.remove_todo(1).await.unwrap());
+        assert!(!store.remove_todo(1).await.unwrap());
+        store
+            .add_rss_link("https://example.com/feed".to_string())
+            .await
+            .unwrap();
+        let date = NaiveDateTime::parse_from_str("2026-10-18 09:30:00", DATE_FORMAT).unwrap();
+        store.set_last_date(Source::Rss, date).await.unwrap();
+
+        // 開き直しても同じ内容が読める
+        let reopened = FileStore::open(&path).unwrap();
+        assert_eq!(reopened.todos().await.unwrap(), vec![todo(2, "review PR")]);
+        // 消した id は使い回さない
+        assert!(reopened.remove_todo(2).await.unwrap());
+        assert_eq!(reopened.next_todo_id().await.unwrap(), 3);
+        assert_eq!(
+            reopened.add_todo(todo(0, "next")).await.unwrap(),
+            todo(3, "next")
+        );
+        assert!(reopened
+            .replace_todo(2, None, Some(todo(2, "review PR")))
+            .await
+            .unwrap());
+        // 戻す前の状態が変わっていれば何もしない
+        assert!(!reopened
+            .replace_todo(2, Some(&todo(2, "review")), None)
+            .await
+            .unwrap());
+        assert!(!reopened
+            .replace_todo(3, None, Some(todo(3, "old")))
+            .await
+            .unwrap());
+        assert_eq!(
+            FileStore::open(&path).unwrap().todos().await.unwrap(),
+            vec![todo(2, "review PR"), todo(3, "next")]
+        );
+        assert_eq!(
+            reopened.rss_links().await.unwrap(),
+            vec!["https://example.com/feed".to_string()]
+        );
+        assert_eq!(reopened.last_date(Source::Rss).await.unwrap(), Some(date));
+        assert_eq!(reopened.last_date(Source::Atproto).await.unwrap(), None);
+        assert!(reopened
+            .remove_rss_link("https://example.com/feed")
+            .await
+            .unwrap());
+        assert_eq!(
+            FileStore::open(&path).unwrap().rss_links().await.unwrap(),
+            Vec::<String>::new()
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mark_reminded() {
+        let path = temp_path("reminded");
+        let store = FileStore::create(&path, Data::default()).unwrap();
+        let due = |id, text, due| Todo {
+            due: Some(due),
+            ..todo(id, text)
+        };
+        store.add_todo(due(0, "review", 100)).await.unwrap();
+        store.add_todo(due(0, "deploy", 100)).await.unwrap();
+        // 通知を送る間に期限を変えたり完了したりしたものには印を付けない
+        store.update_todo(due(2, "deploy", 200)).await.unwrap();
+        assert!(store.mark_reminded(1, 100).await.unwrap());
+        assert!(!store.mark_reminded(2, 100).await.unwrap());
+        assert!(!store.mark_reminded(3, 100).await.unwrap());
+        assert_eq!(
+            store.todos().await.unwrap(),
+            vec![
+                Todo {
+                    reminded: true,
+                    ..due(1, "review", 100)
+                },
+                due(2, "deploy", 200)
+            ]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_invalid() {
+        let path = temp_path("invalid");
+        assert_eq!(
+            FileStore::open(&path).err().map(|err| err.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        fs::write(&path, "todo_message 1 a").unwrap();
+        assert_eq!(
+            FileStore::open(&path).err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
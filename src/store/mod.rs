@@ -0,0 +1,258 @@
+pub mod channel;
+pub mod file;
+mod record;
+
+use std::env;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    client::Context,
+    http::Http,
+    model::{
+        channel::Message,
+        event::MessageUpdateEvent,
+        id::{ChannelId, MessageId},
+    },
+    prelude::TypeMapKey,
+};
+use tracing::info;
+
+use self::channel::ChannelStore;
+use self::file::{Data, FileStore};
+use crate::utils::channel_cache::{self, ChannelCache};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: u64,
+    pub text: String,
+    // 完了した todo は消さずに残しておく
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub done: Option<Completion>,
+    // 作ったユーザーの id。古い todo にはない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<u64>,
+    // 担当するユーザーの id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assignees: Vec<u64>,
+    // 期限。unix 時間 (秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<i64>,
+    // 期限前の通知を送ったか。期限を変えたら戻す
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reminded: bool,
+    // 追加したチャンネルの id。期限の通知をここに送る
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u64>,
+    // 本文の #tag。# は含めない
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // 本文の p1〜p3。1 が最も高い
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+}
+
+// db チャンネルでは 1 件を 1 メッセージ (2000 文字まで) に書くので、本文とタグ、担当者の数を抑える
+pub const MAX_TEXT_CHARS: usize = 1000;
+pub const MAX_TAGS: usize = 10;
+pub const MAX_ASSIGNEES: usize = 10;
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Todo {
+    pub fn new(id: u64, text: String) -> Self {
+        Todo {
+            id,
+            text,
+            done: None,
+            creator: None,
+            assignees: Vec::new(),
+            due: None,
+            reminded: false,
+            channel: None,
+            tags: Vec::new(),
+            priority: None,
+        }
+    }
+
+    // 完了しないまま期限を過ぎた
+    pub fn is_overdue(&self, now: i64) -> bool {
+        self.done.is_none() && self.due.is_some_and(|due| due < now)
+    }
+
+    // 期限が due のまま完了していない。通知した後に変わっていないかを確かめる
+    pub fn still_due(&self, due: i64) -> bool {
+        self.done.is_none() && self.due == Some(due)
+    }
+
+    // 保存できる大きさか確かめる。本文は改行なども含めて JSON で書いたときの長さで数える
+    pub fn check_size(&self) -> Result<(), String> {
+        let text =
+            serde_json::to_string(&self.text).map_or(usize::MAX, |json| json.chars().count() - 2);
+        if text > MAX_TEXT_CHARS {
+            return Err(format!("本文は {} 文字までです", MAX_TEXT_CHARS));
+        }
+        if self.tags.len() > MAX_TAGS {
+            return Err(format!("タグは {} 個までです", MAX_TAGS));
+        }
+        if self.assignees.len() > MAX_ASSIGNEES {
+            return Err(format!("担当者は {} 人までです", MAX_ASSIGNEES));
+        }
+        Ok(())
+    }
+
+    // 作ったか担当している
+    pub fn belongs_to(&self, user_id: u64) -> bool {
+        self.creator == Some(user_id) || self.assignees.contains(&user_id)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Completion {
+    // unix 時間 (秒)
+    pub at: i64,
+    // 完了したユーザーの id
+    pub by: u64,
+}
+
+// 次に使う todo の id。消した todo の id も使い回さないよう、記録してある counter より小さくしない
+pub fn next_todo_id(counter: u64, todos: &[Todo]) -> u64 {
+    todos
+        .iter()
+        .map(|todo| todo.id + 1)
+        .max()
+        .unwrap_or(1)
+        .max(counter)
+}
+
+// 最後に取得した日時を記録している取得元
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source {
+    Rss,
+    Atproto,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::Rss => "rss",
+            Source::Atproto => "atproto",
+        }
+    }
+}
+
+// 永続化するデータの読み書き。db チャンネルとローカルのファイルの実装がある
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn todos(&self) -> io::Result<Vec<Todo>>;
+    // todo.id は使わず、新しい id を振って追加する。同時に呼ばれても同じ id は振らない
+    async fn add_todo(&self, todo: Todo) -> io::Result<Todo>;
+    // 次に振る id
+    async fn next_todo_id(&self) -> io::Result<u64>;
+    // 同じ id の todo を置き換える。見つからなければ false
+    async fn update_todo(&self, todo: Todo) -> io::Result<bool>;
+    async fn remove_todo(&self, id: u64) -> io::Result<bool>;
+    // id の todo が expected (None ならない状態) のままなら todo に置き換える。None なら消す。
+    // 変わっていれば何もせず false。undo で他の変更を上書きしないよう、確かめてから戻す
+    async fn replace_todo(
+        &self,
+        id: u64,
+        expected: Option<&Todo>,
+        todo: Option<Todo>,
+    ) -> io::Result<bool>;
+    // 期限の通知を送った印だけを付ける。完了したか期限が due から変わっていれば何もせず false
+    async fn mark_reminded(&self, id: u64, due: i64) -> io::Result<bool>;
+
+    async fn rss_links(&self) -> io::Result<Vec<String>>;
+    async fn add_rss_link(&self, link: String) -> io::Result<()>;
+    async fn remove_rss_link(&self, link: &str) -> io::Result<bool>;
+
+    async fn last_date(&self, source: Source) -> io::Result<Option<NaiveDateTime>>;
+    async fn set_last_date(&self, source: Source, date: NaiveDateTime) -> io::Result<()>;
+
+    // gateway のイベントで db チャンネルの変更を知らせる。db チャンネルを使わない実装では何もしない
+    async fn message_created(&self, _message: &Message) {}
+    async fn message_updated(&self, _event: &MessageUpdateEvent) {}
+    async fn messages_deleted(&self, _channel_id: ChannelId, _message_ids: &[MessageId]) {}
+}
+
+pub struct StoreKey;
+
+impl TypeMapKey for StoreKey {
+    type Value = Arc<dyn Store>;
+}
+
+// db チャンネルの内容を全て読み出す
+async fn export(store: &dyn Store) -> io::Result<Data> {
+    let mut data = Data {
+        next_todo_id: store.next_todo_id().await?,
+        todos: store.todos().await?,
+        rss_links: store.rss_links().await?,
+        ..Data::default()
+    };
+    for source in [Source::Rss, Source::Atproto] {
+        if let Some(date) = store.last_date(source).await? {
+            data.set_last_date(source, date);
+        }
+    }
+    Ok(data)
+}
+
+// RINTON_STORE=file ならローカルのファイル (RINTON_STORE_PATH) に保存する。
+// ファイルがまだなければ、最初に一度だけ db チャンネルの内容を移行する
+pub async fn open(http: Arc<Http>, channels: Arc<ChannelCache>) -> io::Result<Arc<dyn Store>> {
+    let channel = ChannelStore::new(http, channels);
+    if env::var("RINTON_STORE").as_deref() != Ok("file") {
+        return Ok(Arc::new(channel));
+    }
+
+    let path = env::var("RINTON_STORE_PATH").unwrap_or_else(|_| "rinton_store.json".to_string());
+    if Path::new(&path).exists() {
+        return Ok(Arc::new(FileStore::open(path)?));
+    }
+
+    info!("migrating the db channel to {}", path);
+    let data = export(&channel).await?;
+    info!(
+        "migrated {} todos and {} rss links",
+        data.todos.len(),
+        data.rss_links.len()
+    );
+    Ok(Arc::new(FileStore::create(path, data)?))
+}
+
+pub async fn get(ctx: &Context) -> Arc<dyn Store> {
+    let data = ctx.data.read().await;
+    match data.get::<StoreKey>() {
+        Some(store) => store.clone(),
+        None => Arc::new(ChannelStore::new(
+            ctx.http.clone(),
+            channel_cache::get(ctx).await,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_todo_id() {
+        let todos = |ids: &[u64]| {
+            ids.iter()
+                .map(|&id| Todo::new(id, String::new()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(next_todo_id(0, &[]), 1);
+        assert_eq!(next_todo_id(0, &todos(&[3, 1])), 4);
+        // 消した 5, 6 は使わない
+        assert_eq!(next_todo_id(7, &todos(&[3, 1])), 7);
+        assert_eq!(next_todo_id(2, &todos(&[3])), 4);
+    }
+}
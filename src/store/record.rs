@@ -0,0 +1,170 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// db チャンネルの 1 メッセージ分の記録は `{prefix} v{version} {json}` の形で書く。
+// prefix は index で種類を見分けるために残し、中身はフィールドを増やしても読めるよう JSON にする
+pub const VERSION: u32 = 1;
+
+#[derive(PartialEq, Debug)]
+pub enum DecodeError {
+    // 別の種類の記録
+    Prefix,
+    // このバージョンでは読めない新しい形式
+    Version(u32),
+    Payload(String),
+    // バージョンのない古い形式として読めなかった
+    Legacy,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Prefix => write!(f, "prefix が違います"),
+            DecodeError::Version(version) => write!(f, "v{} は読めません", version),
+            DecodeError::Payload(why) => write!(f, "中身を読めません: {}", why),
+            DecodeError::Legacy => write!(f, "古い形式として読めません"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Decoded<T> {
+    pub value: T,
+    // 古い形式で書かれていたので書き直した方がよい
+    pub legacy: bool,
+}
+
+pub fn encode<T: Serialize>(prefix: &str, value: &T) -> String {
+    // Serialize の derive した構造体なら失敗しない
+    let json = serde_json::to_string(value).expect("record must serialize");
+    format!("{} v{} {}", prefix, VERSION, json)
+}
+
+// `v1` のような単語ならバージョンとして読む
+fn version(word: &str) -> Option<u32> {
+    let digits = word.strip_prefix('v')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// バージョンのない記録は legacy に prefix より後ろを渡して読む
+pub fn decode<T: DeserializeOwned>(
+    prefix: &str,
+    content: &str,
+    legacy: impl Fn(&str) -> Option<T>,
+) -> Result<Decoded<T>, DecodeError> {
+    let rest = match content.strip_prefix(prefix) {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest.trim_start(),
+        _ => return Err(DecodeError::Prefix),
+    };
+    let (word, payload) = rest.split_once(' ').unwrap_or((rest, ""));
+    match version(word) {
+        Some(VERSION) => serde_json::from_str(payload)
+            .map(|value| Decoded {
+                value,
+                legacy: false,
+            })
+            .map_err(|why| DecodeError::Payload(why.to_string())),
+        Some(version) => Err(DecodeError::Version(version)),
+        None => legacy(rest)
+            .map(|value| Decoded {
+                value,
+                legacy: true,
+            })
+            .ok_or(DecodeError::Legacy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    struct Note {
+        id: u64,
+        text: String,
+    }
+
+    fn legacy_note(rest: &str) -> Option<Note> {
+        let (id, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        Some(Note {
+            id: id.parse().ok()?,
+            text: text.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_record() {
+        let note = Note {
+            id: 3,
+            text: "複数の 単語\nと改行".to_string(),
+        };
+        let content = encode("note", &note);
+        assert_eq!(content, r#"note v1 {"id":3,"text":"複数の 単語\nと改行"}"#);
+        assert_eq!(
+            decode("note", &content, legacy_note),
+            Ok(Decoded {
+                value: note,
+                legacy: false
+            })
+        );
+
+        // 知らないフィールドは読み飛ばす
+        assert_eq!(
+            decode(
+                "note",
+                r#"note v1 {"id":1,"text":"a","due":"tomorrow"}"#,
+                legacy_note
+            )
+            .map(|decoded| decoded.value.id),
+            Ok(1)
+        );
+
+        assert_eq!(
+            decode("note", "note 5 古い 形式", legacy_note),
+            Ok(Decoded {
+                value: Note {
+                    id: 5,
+                    text: "古い 形式".to_string()
+                },
+                legacy: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_record_error() {
+        assert_eq!(
+            decode("note", "notes v1 {}", legacy_note),
+            Err(DecodeError::Prefix)
+        );
+        assert_eq!(
+            decode("note", "rss_link v1 {}", legacy_note),
+            Err(DecodeError::Prefix)
+        );
+        assert_eq!(
+            decode("note", r#"note v2 {"id":1}"#, legacy_note),
+            Err(DecodeError::Version(2))
+        );
+        assert!(matches!(
+            decode("note", r#"note v1 {"id":"x"}"#, legacy_note),
+            Err(DecodeError::Payload(_))
+        ));
+        assert!(matches!(
+            decode("note", "note v1", legacy_note),
+            Err(DecodeError::Payload(_))
+        ));
+        assert_eq!(
+            decode("note", "note abc", legacy_note),
+            Err(DecodeError::Legacy)
+        );
+        assert_eq!(
+            decode("note", "note", legacy_note),
+            Err(DecodeError::Legacy)
+        );
+    }
+}
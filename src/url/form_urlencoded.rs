@@ -0,0 +1,84 @@
+use super::percent_encoding::{percent_decode_lossy, percent_encode, EncodeSet};
+
+// application/x-www-form-urlencoded
+// https://url.spec.whatwg.org/#application/x-www-form-urlencoded
+
+/// key / value を 1 つ encode する。空白は '+' になる
+pub fn byte_serialize(input: &str) -> String {
+    percent_encode(input, EncodeSet::Form).replace("%20", "+")
+}
+
+/// (key, value) の組を順番どおりに `a=1&b=2` の形式にする
+/// # Example
+/// ```
+/// let query = serialize(&[("q", "hello world"), ("lang", "ja")]);
+/// assert_eq!(query, "q=hello+world&lang=ja");
+/// ```
+pub fn serialize<K: AsRef<str>, V: AsRef<str>>(pairs: &[(K, V)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| {
+            format!(
+                "{}={}",
+                byte_serialize(k.as_ref()),
+                byte_serialize(v.as_ref())
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// `a=1&b=2` を出現順のまま (key, value) に分解して decode する
+pub fn parse(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut pair = pair.splitn(2, '=');
+            let key = pair.next().unwrap_or("");
+            let value = pair.next().unwrap_or("");
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(input: &str) -> String {
+    percent_decode_lossy(&input.replace('+', " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            serialize(&[("q", "hello world"), ("lang", "ja")]),
+            "q=hello+world&lang=ja"
+        );
+        assert_eq!(
+            serialize(&[("q", "a+b&c=d"), ("tag", "日本")]),
+            "q=a%2Bb%26c%3Dd&tag=%E6%97%A5%E6%9C%AC"
+        );
+        assert_eq!(serialize::<&str, &str>(&[]), "");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("tag=a&q=hello+world%21&tag=b&empty&&%E6%97%A5=%"),
+            vec![
+                ("tag".to_string(), "a".to_string()),
+                ("q".to_string(), "hello world!".to_string()),
+                ("tag".to_string(), "b".to_string()),
+                ("empty".to_string(), "".to_string()),
+                ("日".to_string(), "%".to_string()),
+            ]
+        );
+        // '+' を encode したものは '+' のまま戻る
+        assert_eq!(
+            parse(&serialize(&[("q", "a+b c")])),
+            vec![("q".to_string(), "a+b c".to_string())]
+        );
+    }
+}
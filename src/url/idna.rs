@@ -0,0 +1,231 @@
+// 国際化ドメイン名 (IDN) を DNS / TLS で使える ASCII 形式と表示用の Unicode 形式に変換する
+// Punycode は RFC 3492 に従う。UTS #46 の mapping は小文字化と全角ピリオドの置き換えだけ行う。
+// https://datatracker.ietf.org/doc/html/rfc3492
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+const ACE_PREFIX: &str = "xn--";
+
+fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        T_MIN
+    } else if k >= bias + T_MAX {
+        T_MAX
+    } else {
+        k - bias
+    }
+}
+
+fn encode_digit(d: u32) -> char {
+    match d {
+        0..=25 => (b'a' + d as u8) as char,
+        _ => (b'0' + (d - 26) as u8) as char,
+    }
+}
+
+fn decode_digit(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32),
+        'A'..='Z' => Some(c as u32 - 'A' as u32),
+        '0'..='9' => Some(c as u32 - '0' as u32 + 26),
+        _ => None,
+    }
+}
+
+/// Unicode の文字列を Punycode にする。"xn--" は付けない
+pub fn punycode_encode(input: &str) -> Option<String> {
+    let input = input.chars().map(|c| c as u32).collect::<Vec<_>>();
+    let mut output = input
+        .iter()
+        .filter(|&&c| c < INITIAL_N)
+        .map(|&c| char::from_u32(c).unwrap_or_default())
+        .collect::<String>();
+
+    let basic_len = output.len() as u32;
+    let mut handled = basic_len;
+    if basic_len > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+
+    while (handled as usize) < input.len() {
+        let m = *input.iter().filter(|&&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+
+        for &c in &input {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic_len);
+                delta = 0;
+                handled += 1;
+            }
+        }
+
+        delta += 1;
+        n += 1;
+    }
+
+    Some(output)
+}
+
+/// Punycode を Unicode の文字列に戻す。"xn--" は取り除いてから渡す
+pub fn punycode_decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut output = basic.chars().collect::<Vec<char>>();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut chars = extended.chars().peekable();
+
+    while chars.peek().is_some() {
+        let old_i = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = decode_digit(chars.next()?)?;
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+
+    Some(output.into_iter().collect())
+}
+
+// 全角・半角のピリオドもラベルの区切りとして扱う
+fn split_labels(domain: &str) -> impl Iterator<Item = &str> {
+    domain.split(['.', '。', '．', '｡'])
+}
+
+/// ドメイン名を接続に使う ASCII 形式 (A-label) にする
+/// # Example
+/// ```
+/// assert_eq!(domain_to_ascii("日本語.jp"), "xn--wgv71a119e.jp");
+/// ```
+pub fn domain_to_ascii(domain: &str) -> String {
+    if domain.is_ascii() {
+        return domain.to_ascii_lowercase();
+    }
+
+    split_labels(domain)
+        .map(|label| {
+            let label = label.to_lowercase();
+            if label.is_ascii() {
+                return label;
+            }
+            match punycode_encode(&label) {
+                Some(encoded) => format!("{}{}", ACE_PREFIX, encoded),
+                None => label,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// ドメイン名を表示用の Unicode 形式 (U-label) にする。decode できないラベルはそのまま残す
+pub fn domain_to_unicode(domain: &str) -> String {
+    split_labels(domain)
+        .map(|label| {
+            let lower = label.to_ascii_lowercase();
+            match lower.strip_prefix(ACE_PREFIX) {
+                Some(encoded) => punycode_decode(encoded).unwrap_or(lower),
+                None => label.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_punycode() {
+        let cases = vec![
+            ("日本語", "wgv71a119e"),
+            ("bücher", "bcher-kva"),
+            ("münchen", "mnchen-3ya"),
+            ("例え", "r8jz45g"),
+            // RFC 3492 7.1 (L) Japanese
+            ("3年b組金八先生", "3b-ww4c5e180e575a65lsy2b"),
+            ("abc", "abc-"),
+        ];
+        for (unicode, ascii) in cases {
+            assert_eq!(punycode_encode(unicode), Some(ascii.to_string()));
+            assert_eq!(punycode_decode(ascii), Some(unicode.to_string()));
+        }
+
+        assert_eq!(punycode_decode("!!"), None);
+    }
+
+    #[test]
+    fn test_domain_to_ascii() {
+        assert_eq!(domain_to_ascii("日本語.jp"), "xn--wgv71a119e.jp");
+        assert_eq!(domain_to_ascii("例え。テスト"), "xn--r8jz45g.xn--zckzah");
+        assert_eq!(domain_to_ascii("Example.COM"), "example.com");
+        assert_eq!(domain_to_ascii("BÜCHER.de"), "xn--bcher-kva.de");
+    }
+
+    #[test]
+    fn test_domain_to_unicode() {
+        assert_eq!(domain_to_unicode("xn--wgv71a119e.jp"), "日本語.jp");
+        assert_eq!(domain_to_unicode("XN--bcher-kva.de"), "bücher.de");
+        assert_eq!(domain_to_unicode("example.com"), "example.com");
+        assert_eq!(domain_to_unicode("xn--!!.com"), "xn--!!.com");
+    }
+}
@@ -1 +1,4 @@
+pub mod form_urlencoded;
+pub mod idna;
+pub mod percent_encoding;
 pub mod url;
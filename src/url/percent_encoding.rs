@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::fmt;
+
+// WHATWG URL Standard の percent-encode set
+// https://url.spec.whatwg.org/#percent-encoded-bytes
+// 後ろのものほど多くの文字を encode する。ASCII 以外は常に encode する。
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EncodeSet {
+    Fragment,
+    Query,
+    Path,
+    UserInfo,
+    Component,
+    Form,
+}
+
+impl EncodeSet {
+    fn contains(&self, b: u8) -> bool {
+        if !(0x20..=0x7e).contains(&b) {
+            return true;
+        }
+        let extra: &[u8] = match self {
+            EncodeSet::Fragment => b" \"<>`",
+            EncodeSet::Query => b" \"#<>",
+            EncodeSet::Path => b" \"#<>?`{}",
+            EncodeSet::UserInfo => b" \"#<>?`{}/:;=@[\\]^|",
+            EncodeSet::Component => b" \"#<>?`{}/:;=@[\\]^|$%&+,",
+            EncodeSet::Form => b" \"#<>?`{}/:;=@[\\]^|$%&+,!'()~",
+        };
+        extra.contains(&b)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    // '%' の後ろに 16 進数 2 桁が続いていない。値は input 中の byte 位置
+    InvalidEscape(usize),
+    InvalidUtf8,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidEscape(pos) => write!(f, "invalid percent escape at {}", pos),
+            DecodeError::InvalidUtf8 => write!(f, "decoded bytes are not utf-8"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// set に含まれる文字を %XX に変換する
+/// # Example
+/// ```
+/// assert_eq!(percent_encode("日本 語", EncodeSet::Component), "%E6%97%A5%E6%9C%AC%20%E8%AA%9E");
+/// ```
+pub fn percent_encode(input: &str, set: EncodeSet) -> String {
+    let mut out = String::with_capacity(input.len());
+    for &b in input.as_bytes() {
+        if set.contains(b) {
+            out.push('%');
+            out.push(HEX[(b >> 4) as usize] as char);
+            out.push(HEX[(b & 0x0f) as usize] as char);
+        } else {
+            out.push(b as char);
+        }
+    }
+    out
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+// bytes[i] が '%' のとき、続く 2 文字を 1 byte として読む
+fn decode_escape(bytes: &[u8], i: usize) -> Option<u8> {
+    let h = hex_value(*bytes.get(i + 1)?)?;
+    let l = hex_value(*bytes.get(i + 2)?)?;
+    Some((h << 4) | l)
+}
+
+fn decode_bytes(input: &str, strict: bool) -> Result<Vec<u8>, DecodeError> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            match decode_escape(bytes, i) {
+                Some(b) => {
+                    out.push(b);
+                    i += 3;
+                    continue;
+                }
+                None if strict => return Err(DecodeError::InvalidEscape(i)),
+                None => {}
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// %XX を decode する。不正な escape や UTF-8 にならない場合はエラーを返す
+#[allow(dead_code)]
+pub fn percent_decode(input: &str) -> Result<String, DecodeError> {
+    let bytes = decode_bytes(input, true)?;
+    String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// %XX を decode する。不正な escape はそのまま残し、不正な UTF-8 は U+FFFD に置き換える
+pub fn percent_decode_lossy(input: &str) -> String {
+    // strict でなければエラーにはならない
+    let bytes = decode_bytes(input, false).unwrap_or_default();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("a b", EncodeSet::Fragment), "a%20b");
+        assert_eq!(percent_encode("a#b?c", EncodeSet::Query), "a%23b?c");
+        assert_eq!(percent_encode("/a b/c?d", EncodeSet::Path), "/a%20b/c%3Fd");
+        assert_eq!(
+            percent_encode("user:pass", EncodeSet::UserInfo),
+            "user%3Apass"
+        );
+        assert_eq!(
+            percent_encode("a&b=c+d/e", EncodeSet::Component),
+            "a%26b%3Dc%2Bd%2Fe"
+        );
+        assert_eq!(percent_encode("it's~", EncodeSet::Form), "it%27s%7E");
+        assert_eq!(
+            percent_encode("日本 語", EncodeSet::Component),
+            "%E6%97%A5%E6%9C%AC%20%E8%AA%9E"
+        );
+        assert_eq!(percent_encode("-._azAZ09", EncodeSet::Form), "-._azAZ09");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(
+            percent_decode("%E6%97%A5%E6%9C%AC%20%e8%aa%9e"),
+            Ok("日本 語".to_string())
+        );
+        assert_eq!(percent_decode("a+b"), Ok("a+b".to_string()));
+        assert_eq!(percent_decode("100%"), Err(DecodeError::InvalidEscape(3)));
+        assert_eq!(percent_decode("%4"), Err(DecodeError::InvalidEscape(0)));
+        assert_eq!(percent_decode("%zz"), Err(DecodeError::InvalidEscape(0)));
+        assert_eq!(percent_decode("%FF"), Err(DecodeError::InvalidUtf8));
+        // 末尾の escape も decode できる
+        assert_eq!(percent_decode("a%41"), Ok("aA".to_string()));
+    }
+
+    #[test]
+    fn test_percent_decode_lossy() {
+        assert_eq!(percent_decode_lossy("100%"), "100%");
+        assert_eq!(percent_decode_lossy("%zz%41"), "%zzA");
+        assert_eq!(percent_decode_lossy("a%FFb"), "a\u{fffd}b");
+        assert_eq!(percent_decode_lossy("%E6%97%A5"), "日");
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serenity::{
+    client::Context,
+    http::Http,
+    model::{
+        channel::{Channel, GuildChannel},
+        guild::Guild,
+        id::{ChannelId, GuildId},
+    },
+    prelude::TypeMapKey,
+};
+
+type Channels = HashMap<ChannelId, GuildChannel>;
+
+// サーバーごとのチャンネル一覧。
+// cache feature を使っていないので、gateway の guild_create とチャンネルのイベントで自前で更新する。
+// まだ届いていないサーバーは初回だけ REST で取得する
+pub struct ChannelCache {
+    http: Arc<Http>,
+    guilds: RwLock<HashMap<GuildId, Channels>>,
+}
+
+impl ChannelCache {
+    pub fn new(http: Arc<Http>) -> Self {
+        ChannelCache {
+            http,
+            guilds: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<GuildId, Channels>> {
+        self.guilds
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<GuildId, Channels>> {
+        self.guilds
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub async fn channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> serenity::Result<Option<GuildChannel>> {
+        if let Some(channels) = self.read().get(&guild_id) {
+            return Ok(channels.get(&channel_id).cloned());
+        }
+
+        // 取得中にイベントで埋まっていたらそちらの方が新しい
+        let fetched = guild_id.channels(&self.http).await?;
+        let mut guilds = self.write();
+        let channels = guilds.entry(guild_id).or_insert(fetched);
+        Ok(channels.get(&channel_id).cloned())
+    }
+
+    pub fn set_guild(&self, guild: &Guild) {
+        let channels = guild
+            .channels
+            .values()
+            .filter_map(|channel| match channel {
+                Channel::Guild(channel) => Some((channel.id, channel.clone())),
+                _ => None,
+            })
+            .collect();
+        self.write().insert(guild.id, channels);
+    }
+
+    pub fn remove_guild(&self, guild_id: GuildId) {
+        self.write().remove(&guild_id);
+    }
+
+    // 一覧をまだ持っていないサーバーのチャンネルは、取得したときに含まれるので何もしない
+    pub fn insert(&self, channel: &GuildChannel) {
+        if let Some(channels) = self.write().get_mut(&channel.guild_id) {
+            channels.insert(channel.id, channel.clone());
+        }
+    }
+
+    pub fn remove(&self, guild_id: GuildId, channel_id: ChannelId) {
+        if let Some(channels) = self.write().get_mut(&guild_id) {
+            channels.remove(&channel_id);
+        }
+    }
+}
+
+pub struct ChannelCacheKey;
+
+impl TypeMapKey for ChannelCacheKey {
+    type Value = Arc<ChannelCache>;
+}
+
+pub async fn get(ctx: &Context) -> Arc<ChannelCache> {
+    let data = ctx.data.read().await;
+    match data.get::<ChannelCacheKey>() {
+        Some(cache) => cache.clone(),
+        None => Arc::new(ChannelCache::new(ctx.http.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: u64, name: &str) -> GuildChannel {
+        serde_json::from_value(serde_json::json!({
+            "id": id.to_string(),
+            "guild_id": "1",
+            "type": 0,
+            "name": name,
+            "position": 0,
+            "permission_overwrites": [],
+            "nsfw": false,
+        }))
+        .unwrap()
+    }
+
+    fn cached(cache: &ChannelCache, id: u64) -> Option<String> {
+        let guilds = cache.read();
+        let channel = guilds.get(&GuildId(1))?.get(&ChannelId(id))?;
+        Some(channel.name.clone())
+    }
+
+    #[tokio::test]
+    async fn test_channel_cache() {
+        let cache = ChannelCache::new(Arc::new(Http::new("")));
+
+        // 一覧を持っていないサーバーには追加しない
+        cache.insert(&channel(10, "general"));
+        assert_eq!(cached(&cache, 10), None);
+
+        cache.write().insert(
+            GuildId(1),
+            HashMap::from([(ChannelId(10), channel(10, "general"))]),
+        );
+        cache.insert(&channel(20, "db"));
+        cache.insert(&channel(10, "random"));
+        assert_eq!(cached(&cache, 10), Some("random".to_string()));
+        assert_eq!(
+            cache
+                .channel(GuildId(1), ChannelId(20))
+                .await
+                .unwrap()
+                .map(|channel| channel.name),
+            Some("db".to_string())
+        );
+
+        cache.remove(GuildId(1), ChannelId(20));
+        assert_eq!(
+            cache
+                .channel(GuildId(1), ChannelId(20))
+                .await
+                .unwrap()
+                .map(|c| c.id),
+            None
+        );
+
+        cache.remove_guild(GuildId(1));
+        assert_eq!(cached(&cache, 10), None);
+    }
+}
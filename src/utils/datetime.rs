@@ -0,0 +1,151 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone as _, Utc};
+
+// 日時の表示に使うタイムゾーン。夏時間は扱わず、略称ごとに固定の時差を持つ
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimeZone {
+    pub name: &'static str,
+    // UTC から東に何秒ずれているか
+    pub offset: i32,
+}
+
+const HOUR: i32 = 3600;
+
+const TIME_ZONES: [(&str, i32); 10] = [
+    ("UTC", 0),
+    ("GMT", 0),
+    ("JST", 9 * HOUR),
+    ("KST", 9 * HOUR),
+    ("CET", HOUR),
+    ("CEST", 2 * HOUR),
+    ("EST", -5 * HOUR),
+    ("EDT", -4 * HOUR),
+    ("PST", -8 * HOUR),
+    ("PDT", -7 * HOUR),
+];
+
+impl TimeZone {
+    /// 日時のリテラルや today はこのタイムゾーンで読む
+    pub fn jst() -> Self {
+        TimeZone {
+            name: "JST",
+            offset: 9 * HOUR,
+        }
+    }
+
+    pub fn find(name: &str) -> Option<Self> {
+        TIME_ZONES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(name, offset)| TimeZone { name, offset })
+    }
+
+    pub fn fixed_offset(&self) -> FixedOffset {
+        // 表の時差はどれも ±24 時間に収まっている
+        FixedOffset::east_opt(self.offset).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+/// `2026-10-18` や `2026-10-18T09:30:00` を JST として読み、UNIX 時間の秒数を返す
+pub fn parse_literal(literal: &str) -> Option<i64> {
+    let literal = literal.replacen(' ', "T", 1);
+    let naive = match literal.len() {
+        10 => NaiveDate::parse_from_str(&literal, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?,
+        16 => NaiveDateTime::parse_from_str(&literal, "%Y-%m-%dT%H:%M").ok()?,
+        _ => NaiveDateTime::parse_from_str(&literal, "%Y-%m-%dT%H:%M:%S").ok()?,
+    };
+    let datetime = TimeZone::jst()
+        .fixed_offset()
+        .from_local_datetime(&naive)
+        .single()?;
+    Some(datetime.timestamp())
+}
+
+/// その日の 0 時
+pub fn start_of_day(now: DateTime<Utc>, time_zone: &TimeZone) -> Option<i64> {
+    let local = now.with_timezone(&time_zone.fixed_offset());
+    let midnight = local.date_naive().and_hms_opt(0, 0, 0)?;
+    let datetime = time_zone
+        .fixed_offset()
+        .from_local_datetime(&midnight)
+        .single()?;
+    Some(datetime.timestamp())
+}
+
+/// `2026-12-02 (Wed) 09:00:00 JST` の形式。範囲外なら None
+pub fn format(timestamp: i64, time_zone: &TimeZone) -> Option<String> {
+    let datetime = DateTime::from_timestamp(timestamp, 0)?.with_timezone(&time_zone.fixed_offset());
+    Some(format!(
+        "{} {}",
+        datetime.format("%Y-%m-%d (%a) %H:%M:%S"),
+        time_zone.name
+    ))
+}
+
+/// 秒数を `63d 4h 5min 6s` のように分ける。0 の部分は省く
+pub fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let mut rest = seconds.unsigned_abs();
+    let mut parts = Vec::new();
+    for (unit, size) in [("d", 86400), ("h", 3600), ("min", 60), ("s", 1)] {
+        if rest >= size {
+            parts.push(format!("{}{}", rest / size, unit));
+            rest %= size;
+        }
+    }
+    if parts.is_empty() {
+        return "0s".to_string();
+    }
+    format!("{}{}", sign, parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_literal() {
+        assert_eq!(parse_literal("1970-01-01"), Some(-9 * 3600));
+        assert_eq!(parse_literal("2023-11-15 07:13:20"), Some(1700000000));
+        assert_eq!(parse_literal("2023-11-15T07:13:20"), Some(1700000000));
+        assert_eq!(parse_literal("2023-11-15T07:13"), Some(1700000000 - 20));
+        assert_eq!(parse_literal("2026-02-30"), None);
+        assert_eq!(parse_literal("2026-10-18T25:00"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format(1700000000, &TimeZone::jst()),
+            Some("2023-11-15 (Wed) 07:13:20 JST".to_string())
+        );
+        assert_eq!(
+            format(1700000000, &TimeZone::find("UTC").unwrap()),
+            Some("2023-11-14 (Tue) 22:13:20 UTC".to_string())
+        );
+        assert_eq!(format(i64::MAX, &TimeZone::jst()), None);
+    }
+
+    #[test]
+    fn test_start_of_day() {
+        // UTC では 11/14 だが JST では 11/15
+        let now = DateTime::from_timestamp(1700000000, 0).unwrap();
+        assert_eq!(
+            start_of_day(now, &TimeZone::jst()),
+            parse_literal("2023-11-15")
+        );
+        assert_eq!(
+            start_of_day(now, &TimeZone::find("UTC").unwrap()),
+            Some(1699920000)
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(13500), "3h 45min");
+        assert_eq!(format_duration(86400 * 63 + 3661), "63d 1h 1min 1s");
+        assert_eq!(format_duration(-90), "-1min 30s");
+    }
+}
@@ -6,9 +6,8 @@ use serenity::client::Context;
 use tracing::error;
 
 use crate::http::client::HttpClient;
-use std::error::Error;
-
-use super::get_db_channel::get_db_channel;
+use crate::store::{self, Source};
+use crate::url::form_urlencoded;
 
 #[derive(Deserialize)]
 #[allow(non_snake_case)]
@@ -142,8 +141,8 @@ async fn get_feed() -> Result<Body, Box<dyn std::error::Error>> {
     let mut client = HttpClient::new();
     let params = "at://did:plc:c2f75sprlocrelfiftzblj6z/app.bsky.feed.generator/aaair5qf7emhe";
     let url = format!(
-        "https://bsky.social/xrpc/app.bsky.feed.getFeed?feed={}",
-        params
+        "https://bsky.social/xrpc/app.bsky.feed.getFeed?{}",
+        form_urlencoded::serialize(&[("feed", params)])
     );
 
     let response = match client
@@ -179,8 +178,11 @@ async fn get_feed() -> Result<Body, Box<dyn std::error::Error>> {
 
 pub async fn fetch_atproto(ctx: &Context) -> Result<Vec<Feed>, Box<dyn std::error::Error>> {
     let res = get_feed().await?;
-    let last_date = get_last_date(ctx).await?;
-    let last_date = NaiveDateTime::parse_from_str(&last_date, "%Y-%m-%d %H:%M:%S")?;
+    // 一度も取得していなければ今から取得したことにする
+    let last_date = match store::get(ctx).await.last_date(Source::Atproto).await? {
+        Some(last_date) => last_date,
+        None => chrono::Utc::now().naive_utc(),
+    };
 
     let feeds = res
         .feed
@@ -197,36 +199,3 @@ pub async fn fetch_atproto(ctx: &Context) -> Result<Vec<Feed>, Box<dyn std::erro
 
     Ok(feeds)
 }
-
-async fn get_last_date(ctx: &Context) -> Result<String, Box<dyn Error>> {
-    let db_channel = get_db_channel(ctx).await?;
-
-    let messages = match db_channel
-        .messages(&ctx.http, |retriever| retriever.limit(100))
-        .await
-    {
-        Ok(messages) => messages
-            .into_iter()
-            .filter(|message| message.content.starts_with("atproto_last_date"))
-            .collect::<Vec<_>>(),
-        Err(_) => {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "DBチャンネルが見つかりません。",
-            )))
-        }
-    };
-
-    // last_date は基本的に1つしか存在しないはずなので、0番目を取得
-    let last_date = match messages.first() {
-        // rss_last_date %Y-%m-%d %H:%M:%S という形式で保存されているので、それの日付部分だけを取得
-        // nth(1) は %Y-%m-%d の部分, nth(2) は %H:%M:%S の部分
-        Some(message) => {
-            let date = message.content.split_whitespace().nth(1).unwrap();
-            let time = message.content.split_whitespace().nth(2).unwrap();
-            format!("{} {}", date, time)
-        }
-        None => chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-    };
-    Ok(last_date)
-}
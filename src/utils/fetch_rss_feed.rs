@@ -1,46 +1,12 @@
-use chrono::{DateTime, NaiveDateTime};
+use chrono::DateTime;
 use rss::{Channel, Item};
 use serenity::client::Context;
 use std::error::Error;
 use tracing::{info, warn};
 
-use super::get_db_channel::get_db_channel;
 use crate::http::client::HttpClient;
-
-// rss のリストを #db チャンネルから `rss_link` という prefix がついてるものを取得。
-async fn get_rss_list(ctx: &Context) -> Result<Vec<String>, Box<dyn Error>> {
-    let db_channel = get_db_channel(ctx).await?;
-
-    let messages = match db_channel
-        .messages(&ctx.http, |retriever| retriever.limit(100))
-        .await
-    {
-        Ok(messages) => messages
-            .into_iter()
-            .filter(|message| message.content.starts_with("rss_link"))
-            .collect::<Vec<_>>(),
-        Err(_) => {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "DBチャンネルが見つかりません。",
-            )))
-        }
-    };
-
-    let rss_list = messages
-        .iter()
-        .map(|message| {
-            message
-                .content
-                .split_whitespace()
-                .nth(1)
-                .unwrap()
-                .to_string()
-        })
-        .collect::<Vec<String>>();
-
-    Ok(rss_list)
-}
+use crate::store::{self, Source};
+use crate::url::url::Url;
 
 async fn fetch_feed(url: String) -> Result<Channel, Box<dyn Error>> {
     let client = HttpClient::new();
@@ -58,49 +24,21 @@ async fn fetch_feed(url: String) -> Result<Channel, Box<dyn Error>> {
     Ok(channel)
 }
 
-async fn get_last_date(ctx: &Context) -> Result<String, Box<dyn Error>> {
-    let db_channel = get_db_channel(ctx).await?;
-
-    let messages = match db_channel
-        .messages(&ctx.http, |retriever| retriever.limit(100))
-        .await
-    {
-        Ok(messages) => messages
-            .into_iter()
-            .filter(|message| message.content.starts_with("rss_last_date"))
-            .collect::<Vec<_>>(),
-        Err(_) => {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "DBチャンネルが見つかりません。",
-            )))
-        }
-    };
-
-    // last_date は基本的に1つしか存在しないはずなので、0番目を取得
-    let last_date = match messages.first() {
-        // rss_last_date %Y-%m-%d %H:%M:%S という形式で保存されているので、それの日付部分だけを取得
-        // nth(1) は %Y-%m-%d の部分, nth(2) は %H:%M:%S の部分
-        Some(message) => {
-            let date = message.content.split_whitespace().nth(1).unwrap();
-            let time = message.content.split_whitespace().nth(2).unwrap();
-            format!("{} {}", date, time)
-        }
-        None => chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-    };
-    Ok(last_date)
-}
-
 pub async fn fetch_rss_feed(ctx: &Context) -> Result<Vec<Item>, Box<dyn Error>> {
-    let rss_list = get_rss_list(ctx).await?;
+    let store = store::get(ctx).await;
+    let rss_list = store.rss_links().await?;
 
-    let last_date = get_last_date(ctx).await?;
-    let last_date = NaiveDateTime::parse_from_str(&last_date, "%Y-%m-%d %H:%M:%S")?;
+    // 一度も取得していなければ今から取得したことにする
+    let last_date = match store.last_date(Source::Rss).await? {
+        Some(last_date) => last_date,
+        None => chrono::Utc::now().naive_utc(),
+    };
     // ラグ対策として半日巻き戻す
     let last_date = last_date - chrono::Duration::hours(12);
 
     let mut items = Vec::new();
     for url in rss_list {
+        let base = Url::parse(&url);
         let channel = match fetch_feed(url).await {
             Ok(channel) => channel,
             Err(_) => continue,
@@ -128,7 +66,12 @@ pub async fn fetch_rss_feed(ctx: &Context) -> Result<Vec<Item>, Box<dyn Error>>
                     "new feed: {}",
                     item.title.as_ref().unwrap_or(&"".to_string())
                 );
-                items.push(item.clone());
+                let mut item = item.clone();
+                // 相対リンクのままだと Discord で開けないので feed の URL を基準に解決する
+                if let Some(link) = item.link.as_ref() {
+                    item.set_link(base.join(link).to_string());
+                }
+                items.push(item);
             }
         }
     }
@@ -1,16 +1,15 @@
-use serenity::{
-    client::Context,
-    model::{
-        channel::GuildChannel,
-        id::{ChannelId, GuildId},
-    },
+use serenity::model::{
+    channel::GuildChannel,
+    id::{ChannelId, GuildId},
 };
 use std::env;
 use std::error::Error;
 use tracing::error;
 
+use super::channel_cache::ChannelCache;
+
 // db チャンネルを取得
-pub async fn get_db_channel(ctx: &Context) -> Result<GuildChannel, Box<dyn Error>> {
+pub async fn get_db_channel(channels: &ChannelCache) -> Result<GuildChannel, Box<dyn Error>> {
     let guild_id = GuildId(889012300705591307);
 
     let db_channel_id = match env::var("DISCORD_DB_CHANNEL_ID_RINTON_BOT")?.parse::<u64>() {
@@ -24,7 +23,7 @@ pub async fn get_db_channel(ctx: &Context) -> Result<GuildChannel, Box<dyn Error
         }
     };
 
-    let channels = match guild_id.channels(&ctx.http).await {
+    let db_channel = match channels.channel(guild_id, ChannelId(db_channel_id)).await {
         Ok(channel) => channel,
         Err(_) => {
             error!("faild to get channel list");
@@ -35,7 +34,7 @@ pub async fn get_db_channel(ctx: &Context) -> Result<GuildChannel, Box<dyn Error
         }
     };
 
-    let db_channel = match channels.get(&ChannelId(db_channel_id)) {
+    let db_channel = match db_channel {
         Some(channel) => channel,
         None => {
             error!("db channnel is not found");
@@ -46,5 +45,5 @@ pub async fn get_db_channel(ctx: &Context) -> Result<GuildChannel, Box<dyn Error
         }
     };
 
-    Ok(db_channel.clone())
+    Ok(db_channel)
 }
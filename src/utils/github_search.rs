@@ -2,6 +2,7 @@ use serde::Deserialize;
 use tracing::{error, warn};
 
 use crate::http::client::{HttpClient, StatusCode};
+use crate::url::form_urlencoded;
 
 #[derive(Deserialize, Debug)]
 pub struct GoogleItem {
@@ -35,7 +36,13 @@ struct GithubTrend {
 // github api
 // https://docs.github.com/en/rest/reference/search#search-code
 pub async fn github_search(language: &str) -> Result<Vec<GithubTrendItem>, String> {
-    let url = format!("https://api.github.com/search/repositories?q=language:{language}&order=desc&per_page=10&since=daily");
+    let query = form_urlencoded::serialize(&[
+        ("q", format!("language:{language}").as_str()),
+        ("order", "desc"),
+        ("per_page", "10"),
+        ("since", "daily"),
+    ]);
+    let url = format!("https://api.github.com/search/repositories?{query}");
 
     let client = HttpClient::new();
     let response = client.get(&url);
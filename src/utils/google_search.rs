@@ -3,6 +3,7 @@ use std::env;
 use tracing::error;
 
 use crate::http::client::{HttpClient, StatusCode};
+use crate::url::form_urlencoded;
 
 #[derive(Deserialize, Debug)]
 pub struct GoogleItem {
@@ -21,16 +22,10 @@ pub async fn google_search(
     search_type: &str,
     site: &str,
 ) -> Result<Vec<GoogleItem>, String> {
-    // web を明示するとエラーになるので省略する
-    let search_type = if search_type == "image" {
-        format!("&searchType={search_type}")
-    } else {
-        "".to_string()
-    };
-    let site = if site == "" {
-        "".to_string()
+    let q = if site.is_empty() {
+        q.to_string()
     } else {
-        format!("+site:{site}", site = site)
+        format!("{q} site:{site}")
     };
     let search_engine_id = match env::var("SEARCH_ENGINE_ID") {
         Ok(id) => id,
@@ -47,8 +42,21 @@ pub async fn google_search(
         }
     };
 
+    let mut params = vec![
+        ("cx", search_engine_id.as_str()),
+        ("key", api_key.as_str()),
+        ("hl", "ja"),
+    ];
+    // web を明示するとエラーになるので省略する
+    if search_type == "image" {
+        params.push(("searchType", search_type));
+    }
+    params.push(("q", q.as_str()));
+
     let url = format!(
-    "https://www.googleapis.com/customsearch/v1?cx={search_engine_id}&key={api_key}&hl=ja{search_type}&q={q}{site}");
+        "https://www.googleapis.com/customsearch/v1?{}",
+        form_urlencoded::serialize(&params)
+    );
 
     let client = HttpClient::new();
     let result = match client.get(&url).await {
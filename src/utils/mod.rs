@@ -1,8 +1,9 @@
+pub mod channel_cache;
+pub mod datetime;
 pub mod fetch_atproto;
 pub mod fetch_chatgpt;
 pub mod fetch_rss_feed;
 pub mod get_db_channel;
 pub mod github_search;
 pub mod google_search;
-pub mod percent_decode;
 pub mod wikipedia_search;
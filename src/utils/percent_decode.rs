@@ -1,31 +0,0 @@
-fn hex_to_u8(hex: u8) -> u8 {
-    match hex {
-        b'0'..=b'9' => hex - b'0',
-        b'a'..=b'f' => hex - b'a' + 10,
-        b'A'..=b'F' => hex - b'A' + 10,
-        _ => panic!("Invalid hex digit: {}", hex),
-    }
-}
-
-pub fn percent_decode(input: &str) -> String {
-    use std::str;
-
-    let mut out = Vec::new();
-    let mut i = 0;
-    let bytes = input.as_bytes();
-    while i < bytes.len() {
-        match bytes[i] {
-            b'%' if i + 2 < bytes.len() => {
-                let h = hex_to_u8(bytes[i + 1]);
-                let l = hex_to_u8(bytes[i + 2]);
-                out.push((h << 4) | l);
-                i += 3;
-            }
-            b => {
-                out.push(b);
-                i += 1;
-            }
-        }
-    }
-    str::from_utf8(&out).unwrap().to_string()
-}